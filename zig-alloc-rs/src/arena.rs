@@ -0,0 +1,543 @@
+use crate::arena_drop::{DropList, DropNode};
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::zig_allocator::{ZigAllocator, sealed};
+use crate::{CreateError, ZigSmpAllocator};
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// Zig Arena Allocator, reserving its buffers from another Zig allocator
+///
+/// Backed by a [`ZigSmpAllocator`] unless built with [`Self::with_backing`].
+/// `S` tells whether the arena can be shared across threads, see [`Shared`] and [`Local`].
+pub struct ZigArenaAllocator<B: ZigAllocator = ZigSmpAllocator, S: ArenaThreading = Shared> {
+    /// Inner allocator, dropped before `backing`
+    ffi_allocator: FfiAllocator,
+
+    /// Destructors of the values placed with the `*_with_drop` methods
+    drop_list: DropList,
+
+    /// Allocator the arena buffers are reserved from
+    backing: B,
+
+    /// Makes the arena `!Sync` when `S` is [`Local`]
+    threading: PhantomData<S>,
+}
+
+/// Zig Arena SMP Allocator
+pub type ZigArenaSmpAllocator = ZigArenaAllocator<ZigSmpAllocator>;
+
+/// Zig Arena Allocator owned by a single thread at a time
+pub type ZigLocalArenaAllocator<B = ZigSmpAllocator> = ZigArenaAllocator<B, Local>;
+
+/// Whether a [`ZigArenaAllocator`] can be shared across threads
+///
+/// Implemented by [`Shared`] and [`Local`] only.
+pub trait ArenaThreading: threading::Sealed {}
+
+mod threading {
+    pub trait Sealed {
+        /// Whether the Zig side has to lock
+        const THREAD_SAFE: bool;
+    }
+}
+
+/// Arena that can be used from several threads at once, allocations take a lock
+#[derive(Debug)]
+pub struct Shared {
+    _private: (),
+}
+
+/// Arena owned by a single thread at a time, allocations skip locking
+///
+/// The arena can still be sent to another thread, but not shared.
+///
+/// ```compile_fail
+/// let arena = zigalloc::ZigLocalArenaAllocator::new_local();
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| arena.alloc_value(1));
+/// });
+/// ```
+#[derive(Debug)]
+pub struct Local {
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl threading::Sealed for Shared {
+    const THREAD_SAFE: bool = true;
+}
+
+impl ArenaThreading for Shared {}
+
+impl threading::Sealed for Local {
+    const THREAD_SAFE: bool = false;
+}
+
+impl ArenaThreading for Local {}
+
+/// Position in an arena, see [`ZigArenaAllocator::checkpoint`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaCheckpoint {
+    /// Position on the Zig side
+    raw: RawArenaCheckpoint,
+    /// Most recently recorded destructor, as an address
+    drop_head: usize,
+}
+
+/// Position in the arena as reported through the C ABI (`Checkpoint` in Zig)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RawArenaCheckpoint {
+    /// Address of the record allocated in the arena by the checkpoint
+    record: usize,
+    /// Id the record has to hold for the checkpoint to be valid
+    id: u64,
+    /// Number of resets the arena went through
+    generation: usize,
+}
+
+/// Memory held by an arena, see [`ZigArenaAllocator::stats`] (`Stats` in Zig)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArenaStats {
+    /// Bytes reserved from the backing allocator, buffer headers included
+    pub capacity: usize,
+
+    /// Bytes handed out and not freed yet
+    pub used: usize,
+
+    /// Number of buffers reserved from the backing allocator
+    pub chunks: usize,
+
+    /// Bytes neither handed out nor available to the next allocations: tails
+    /// of older buffers, alignment padding, checkpoint records and freed memory
+    /// the arena can't reuse
+    pub wasted: usize,
+}
+
+/// How [`ZigArenaAllocator::reset`] handles the arena capacity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetMode {
+    /// Release every buffer back to the backing allocator
+    FreeAll,
+    /// Keep all the capacity, so the next cycle doesn't go back to the backing allocator
+    RetainCapacity,
+    /// Keep at most this many bytes of capacity
+    RetainWithLimit(usize),
+}
+
+/// Configuration passed to the Zig side (`ArenaSmpAllocator.Config`)
+#[repr(C)]
+struct RawArenaConfig {
+    /// `FfiAllocator` handle the buffers are reserved from
+    backing_allocator: *mut std::ffi::c_void,
+    /// Whether the arena may be used from several threads at once
+    thread_safe: bool,
+}
+
+impl ZigArenaSmpAllocator {
+    /// Create a new arena allocator backed by a [`ZigSmpAllocator`]
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::try_new().expect("failed to create Zig arena SMP allocator")
+    }
+
+    /// Create a new arena allocator backed by a [`ZigSmpAllocator`], reporting why creation failed
+    pub fn try_new() -> Result<Self, CreateError> {
+        Self::try_with_backing(ZigSmpAllocator::try_new()?)
+    }
+}
+
+impl ZigLocalArenaAllocator {
+    /// Create a new single-threaded arena allocator backed by a [`ZigSmpAllocator`]
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new_local`]
+    #[must_use]
+    pub fn new_local() -> Self {
+        Self::try_new_local().expect("failed to create Zig arena SMP allocator")
+    }
+
+    /// Create a new single-threaded arena allocator backed by a [`ZigSmpAllocator`],
+    /// reporting why creation failed
+    pub fn try_new_local() -> Result<Self, CreateError> {
+        Self::try_local_with_backing(ZigSmpAllocator::try_new()?)
+    }
+}
+
+impl<B: ZigAllocator> ZigArenaAllocator<B> {
+    /// Create a new arena allocator reserving its buffers from `backing`
+    ///
+    /// Pass a reference to keep using the backing allocator, for instance to
+    /// check a [`crate::ZigDebugAllocator`] for leaks once the arena is dropped.
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_with_backing`]
+    #[must_use]
+    pub fn with_backing(backing: B) -> Self {
+        Self::try_with_backing(backing).expect("failed to create Zig arena allocator")
+    }
+
+    /// Create a new arena allocator reserving its buffers from `backing`, reporting why creation failed
+    pub fn try_with_backing(backing: B) -> Result<Self, CreateError> {
+        Self::try_create(backing)
+    }
+}
+
+impl<B: ZigAllocator> ZigLocalArenaAllocator<B> {
+    /// Create a new single-threaded arena allocator reserving its buffers from `backing`
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_local_with_backing`]
+    #[must_use]
+    pub fn local_with_backing(backing: B) -> Self {
+        Self::try_local_with_backing(backing).expect("failed to create Zig arena allocator")
+    }
+
+    /// Create a new single-threaded arena allocator reserving its buffers from `backing`,
+    /// reporting why creation failed
+    pub fn try_local_with_backing(backing: B) -> Result<Self, CreateError> {
+        Self::try_create(backing)
+    }
+}
+
+impl<B: ZigAllocator, S: ArenaThreading> ZigArenaAllocator<B, S> {
+    /// Create the Zig arena, locking depending on `S`
+    fn try_create(backing: B) -> Result<Self, CreateError> {
+        let config = RawArenaConfig {
+            backing_allocator: backing.ffi_handle(),
+            thread_safe: S::THREAD_SAFE,
+        };
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status =
+            unsafe { zig_arena_smp_allocator_create_with_config(&config, &mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            drop_list: DropList::new(),
+            backing,
+            threading: PhantomData,
+        })
+    }
+
+    /// Allocator the arena buffers are reserved from
+    #[must_use]
+    pub fn backing(&self) -> &B {
+        &self.backing
+    }
+
+    /// Move `value` into the arena
+    ///
+    /// `Drop` doesn't run for values in the arena, their memory is freed all
+    /// at once on reset or drop. See [`Self::alloc_with_drop`] otherwise.
+    ///
+    /// Panics through [`std::alloc::handle_alloc_error`] if out of memory.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_value<T>(&self, value: T) -> &mut T {
+        let ptr = self.alloc_layout(Layout::new::<T>()).cast::<T>();
+
+        unsafe {
+            ptr.write(value);
+            &mut *ptr.as_ptr()
+        }
+    }
+
+    /// Copy `src` into the arena
+    ///
+    /// Panics through [`std::alloc::handle_alloc_error`] if out of memory.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &mut [T] {
+        let ptr = self.alloc_layout(Layout::for_value(src)).cast::<T>();
+
+        unsafe {
+            ptr.copy_from_nonoverlapping(NonNull::from(src).cast(), src.len());
+            std::slice::from_raw_parts_mut(ptr.as_ptr(), src.len())
+        }
+    }
+
+    /// Allocate a slice of `len` elements in the arena, the element at index `i` being `f(i)`
+    ///
+    /// Panics if the slice size overflows, or through [`std::alloc::handle_alloc_error`]
+    /// if out of memory.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill_with<T>(&self, len: usize, mut f: impl FnMut(usize) -> T) -> &mut [T] {
+        let layout = Layout::array::<T>(len).expect("arena slice size overflows isize::MAX");
+        let ptr = self.alloc_layout(layout).cast::<T>();
+
+        for i in 0..len {
+            unsafe { ptr.add(i).write(f(i)) };
+        }
+
+        unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), len) }
+    }
+
+    /// Move `value` into the arena, running its destructor on reset or drop
+    ///
+    /// Destructors run in reverse allocation order. `T` has to be `Send` and
+    /// `'static`, since the destructor may run on another thread, after any
+    /// borrow it holds has ended.
+    ///
+    /// Panics through [`std::alloc::handle_alloc_error`] if out of memory.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_with_drop<T: Send + 'static>(&self, value: T) -> &mut T {
+        let value = self.alloc_value(value);
+        unsafe { self.record_drop(NonNull::from(&mut *value), 1) };
+        value
+    }
+
+    /// Same as [`Self::alloc_slice_fill_with`], running the destructors on reset or drop
+    ///
+    /// See [`Self::alloc_with_drop`].
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill_with_drop<T: Send + 'static>(
+        &self,
+        len: usize,
+        f: impl FnMut(usize) -> T,
+    ) -> &mut [T] {
+        let values = self.alloc_slice_fill_with(len, f);
+        unsafe { self.record_drop(NonNull::from(&mut *values).cast::<T>(), values.len()) };
+        values
+    }
+
+    /// Record the destructor of `len` values starting at `values`, if they have one
+    ///
+    /// # Safety
+    ///
+    /// `values` must have been allocated in this arena and be initialized.
+    unsafe fn record_drop<T>(&self, values: NonNull<T>, len: usize) {
+        if !std::mem::needs_drop::<T>() || len == 0 {
+            return;
+        }
+
+        let node = self
+            .alloc_layout(Layout::new::<DropNode>())
+            .cast::<DropNode>();
+        unsafe {
+            node.write(DropNode::new(values, len));
+            self.drop_list.push(node);
+        }
+    }
+
+    /// Copy `src` into the arena
+    ///
+    /// Panics through [`std::alloc::handle_alloc_error`] if out of memory.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(&self, src: &str) -> &mut str {
+        let bytes = self.alloc_slice_copy(src.as_bytes());
+        unsafe { std::str::from_utf8_unchecked_mut(bytes) }
+    }
+
+    /// Allocate memory for `layout`, zero-sized layouts don't reach Zig
+    fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        if layout.size() == 0 {
+            let dangling = std::ptr::without_provenance_mut::<u8>(layout.align());
+            return unsafe { NonNull::new_unchecked(dangling) };
+        }
+
+        NonNull::new(self.ffi_allocator.alloc(layout))
+            .unwrap_or_else(|| std::alloc::handle_alloc_error(layout))
+    }
+
+    /// Current position in the arena, to later free what was allocated after it
+    ///
+    /// A small record is allocated in the arena to recognize the checkpoint
+    /// later on, see [`Self::rollback`].
+    ///
+    /// Panics through [`std::alloc::handle_alloc_error`] if out of memory.
+    #[must_use]
+    pub fn checkpoint(&self) -> ArenaCheckpoint {
+        let mut raw = RawArenaCheckpoint {
+            record: 0,
+            id: 0,
+            generation: 0,
+        };
+        let drop_head = self.drop_list.head().addr();
+
+        let allocated =
+            unsafe { zig_arena_smp_allocator_checkpoint(self.ffi_allocator.as_ptr(), &mut raw) };
+        if !allocated {
+            std::alloc::handle_alloc_error(Layout::new::<RawArenaCheckpoint>());
+        }
+
+        ArenaCheckpoint { raw, drop_head }
+    }
+
+    /// Describe the memory currently held by the arena
+    ///
+    /// `capacity` is a good starting point for [`ResetMode::RetainWithLimit`].
+    #[must_use]
+    pub fn stats(&self) -> ArenaStats {
+        let mut stats = ArenaStats::default();
+
+        let found =
+            unsafe { zig_arena_smp_allocator_stats(self.ffi_allocator.as_ptr(), &mut stats) };
+        assert!(found, "invalid Zig arena SMP allocator handle");
+
+        stats
+    }
+
+    /// Free everything allocated after `checkpoint`, running the destructors recorded since
+    ///
+    /// Buffers added since the checkpoint go back to the backing allocator.
+    /// Taking `&mut self` guarantees no allocation of the arena is still borrowed.
+    ///
+    /// Panics if `checkpoint` doesn't come from this arena, or if the arena was
+    /// reset or rolled back past it since.
+    pub fn rollback(&mut self, checkpoint: ArenaCheckpoint) {
+        let allocator = self.ffi_allocator.as_ptr();
+
+        let drop_head = std::ptr::without_provenance_mut(checkpoint.drop_head);
+
+        assert!(
+            unsafe { zig_arena_smp_allocator_can_rollback(allocator, &checkpoint.raw) }
+                && self.drop_list.contains(drop_head),
+            "stale or foreign arena checkpoint"
+        );
+
+        self.drop_list.run_until(drop_head);
+
+        let rolled_back = unsafe { zig_arena_smp_allocator_rollback(allocator, &checkpoint.raw) };
+        debug_assert!(rolled_back);
+    }
+
+    /// Free everything allocated so far, keeping some capacity depending on `mode`
+    ///
+    /// Destructors recorded by the `*_with_drop` methods run first.
+    /// Taking `&mut self` guarantees no allocation of the arena is still borrowed.
+    /// Returns `false` if the retained capacity could not be set up, the arena
+    /// is reset nonetheless.
+    pub fn reset(&mut self, mode: ResetMode) -> bool {
+        let (tag, limit) = match mode {
+            ResetMode::FreeAll => (RESET_MODE_FREE_ALL, 0),
+            ResetMode::RetainCapacity => (RESET_MODE_RETAIN_CAPACITY, 0),
+            ResetMode::RetainWithLimit(limit) => (RESET_MODE_RETAIN_WITH_LIMIT, limit),
+        };
+
+        self.drop_list.run();
+        unsafe { zig_arena_smp_allocator_reset(self.ffi_allocator.as_ptr(), tag, limit) }
+    }
+}
+
+impl<B: ZigAllocator, S: ArenaThreading> Drop for ZigArenaAllocator<B, S> {
+    fn drop(&mut self) {
+        self.drop_list.run();
+    }
+}
+
+/// `ResetModeTag` values in Zig
+const RESET_MODE_FREE_ALL: std::ffi::c_int = 0;
+const RESET_MODE_RETAIN_CAPACITY: std::ffi::c_int = 1;
+const RESET_MODE_RETAIN_WITH_LIMIT: std::ffi::c_int = 2;
+
+impl Default for ZigArenaSmpAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: ZigAllocator, S: ArenaThreading> sealed::Sealed for ZigArenaAllocator<B, S> {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.ffi_allocator.as_ptr()
+    }
+}
+
+impl<B: ZigAllocator, S: ArenaThreading> ZigAllocator for ZigArenaAllocator<B, S> {}
+
+unsafe impl<B: ZigAllocator, S: ArenaThreading> GlobalAlloc for ZigArenaAllocator<B, S> {
+    #[inline]
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        self.ffi_allocator.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        self.ffi_allocator.dealloc(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let new_layout =
+            unsafe { std::alloc::Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.ffi_allocator.realloc(ptr, layout, new_layout)
+    }
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl<B: ZigAllocator, S: ArenaThreading> std::alloc::Allocator for ZigArenaAllocator<B, S> {
+    #[inline]
+    fn allocate(
+        &self,
+        layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        self.ffi_allocator.deallocate(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.shrink(ptr, old_layout, new_layout)
+    }
+}
+
+unsafe extern "C" {
+    fn zig_arena_smp_allocator_create_with_config(
+        config: *const RawArenaConfig,
+        out_allocator: *mut *mut std::ffi::c_void,
+    ) -> CreateStatus;
+
+    fn zig_arena_smp_allocator_checkpoint(
+        allocator: *mut std::ffi::c_void,
+        out_checkpoint: *mut RawArenaCheckpoint,
+    ) -> bool;
+
+    fn zig_arena_smp_allocator_can_rollback(
+        allocator: *mut std::ffi::c_void,
+        checkpoint: *const RawArenaCheckpoint,
+    ) -> bool;
+
+    fn zig_arena_smp_allocator_rollback(
+        allocator: *mut std::ffi::c_void,
+        checkpoint: *const RawArenaCheckpoint,
+    ) -> bool;
+
+    fn zig_arena_smp_allocator_stats(
+        allocator: *mut std::ffi::c_void,
+        out_stats: *mut ArenaStats,
+    ) -> bool;
+
+    fn zig_arena_smp_allocator_reset(
+        allocator: *mut std::ffi::c_void,
+        mode: std::ffi::c_int,
+        limit: usize,
+    ) -> bool;
+}
@@ -0,0 +1,111 @@
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// Destructor recorded for values placed in an arena
+pub(crate) struct DropNode {
+    /// Drops `len` values starting at `values`
+    drop: unsafe fn(values: *mut u8, len: usize),
+    /// First value to drop
+    values: *mut u8,
+    /// Number of values to drop
+    len: usize,
+    /// Node recorded before this one
+    next: *mut DropNode,
+}
+
+impl DropNode {
+    /// Describe the destructor of `len` values of type `T` starting at `values`
+    pub(crate) fn new<T>(values: NonNull<T>, len: usize) -> Self {
+        Self {
+            drop: drop_values::<T>,
+            values: values.as_ptr().cast(),
+            len,
+            next: ptr::null_mut(),
+        }
+    }
+}
+
+unsafe fn drop_values<T>(values: *mut u8, len: usize) {
+    unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(values.cast::<T>(), len)) };
+}
+
+/// Lock-free list of destructors, most recently recorded first
+///
+/// The nodes live in the arena memory, so they must be run before the arena
+/// is reset or destroyed.
+pub(crate) struct DropList {
+    /// Most recently recorded node
+    head: AtomicPtr<DropNode>,
+}
+
+impl DropList {
+    pub(crate) const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Record `node`, to be run by [`Self::run`]
+    ///
+    /// # Safety
+    ///
+    /// `node` must stay valid until the list is run, and describe values that
+    /// are still alive by then.
+    pub(crate) unsafe fn push(&self, node: NonNull<DropNode>) {
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node.as_ptr()).next = head };
+
+            match self.head.compare_exchange_weak(
+                head,
+                node.as_ptr(),
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Most recently recorded node, to later run only the nodes recorded after it
+    pub(crate) fn head(&self) -> *mut DropNode {
+        self.head.load(Ordering::Acquire)
+    }
+
+    /// Whether `node` is still recorded, null standing for the end of the list
+    pub(crate) fn contains(&mut self, node: *mut DropNode) -> bool {
+        let mut current = *self.head.get_mut();
+
+        while current != node {
+            let Some(recorded) = NonNull::new(current) else {
+                return false;
+            };
+            current = unsafe { recorded.as_ref().next };
+        }
+        true
+    }
+
+    /// Run every recorded destructor, most recently recorded first
+    ///
+    /// If a destructor panics, the remaining ones are skipped.
+    pub(crate) fn run(&mut self) {
+        self.run_until(ptr::null_mut());
+    }
+
+    /// Run the destructors recorded after `stop`, most recently recorded first
+    ///
+    /// `stop` has to be a node of the list, or null to run all of them.
+    pub(crate) fn run_until(&mut self, stop: *mut DropNode) {
+        while *self.head.get_mut() != stop {
+            let Some(current) = NonNull::new(*self.head.get_mut()) else {
+                break;
+            };
+
+            let current = unsafe { current.read() };
+            *self.head.get_mut() = current.next;
+
+            unsafe { (current.drop)(current.values, current.len) };
+        }
+    }
+}
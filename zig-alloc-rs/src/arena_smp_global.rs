@@ -22,4 +22,8 @@ unsafe impl GlobalAlloc for ZigGlobalArenaSmpAllocator {
             ALLOCATOR.dealloc(ptr, layout);
         }
     }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { ALLOCATOR.realloc(ptr, layout, new_size) }
+    }
 }
@@ -0,0 +1,150 @@
+use crate::CreateError;
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::zig_allocator::{ZigAllocator, sealed};
+use std::alloc::GlobalAlloc;
+
+/// Zig C Allocator, over libc `malloc`
+///
+/// Wraps `std.heap.c_allocator`, which resizes in place when
+/// `malloc_usable_size` allows it, or `std.heap.raw_c_allocator` when built
+/// with [`Self::raw`], which calls libc as-is.
+///
+/// `raw_c_allocator` only supports alignments up to `max_align_t`, a raw
+/// allocator hands larger ones to `c_allocator`.
+pub struct ZigCAllocator {
+    /// Inner allocator
+    ffi_allocator: FfiAllocator,
+}
+
+/// Configuration passed to the Zig side (`CAllocator.Config`)
+#[repr(C)]
+struct RawCConfig {
+    /// Use `std.heap.raw_c_allocator`
+    raw: bool,
+}
+
+impl ZigCAllocator {
+    /// Create a new C allocator over `std.heap.c_allocator`
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::try_new().expect("failed to create Zig C allocator")
+    }
+
+    /// Create a new C allocator over `std.heap.c_allocator`, reporting why creation failed
+    pub fn try_new() -> Result<Self, CreateError> {
+        Self::try_create(false)
+    }
+
+    /// Create a new C allocator over `std.heap.raw_c_allocator`
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_raw`]
+    #[must_use]
+    pub fn raw() -> Self {
+        Self::try_raw().expect("failed to create Zig C allocator")
+    }
+
+    /// Create a new C allocator over `std.heap.raw_c_allocator`, reporting why creation failed
+    pub fn try_raw() -> Result<Self, CreateError> {
+        Self::try_create(true)
+    }
+
+    /// Create a new C allocator, raw or not
+    fn try_create(raw: bool) -> Result<Self, CreateError> {
+        let config = RawCConfig { raw };
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe { zig_c_allocator_create(&config, &mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+        })
+    }
+}
+
+impl Default for ZigCAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl sealed::Sealed for ZigCAllocator {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.ffi_allocator.as_ptr()
+    }
+}
+
+impl ZigAllocator for ZigCAllocator {}
+
+unsafe impl GlobalAlloc for ZigCAllocator {
+    #[inline]
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        self.ffi_allocator.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        self.ffi_allocator.dealloc(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let new_layout =
+            unsafe { std::alloc::Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.ffi_allocator.realloc(ptr, layout, new_layout)
+    }
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl std::alloc::Allocator for ZigCAllocator {
+    #[inline]
+    fn allocate(
+        &self,
+        layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        self.ffi_allocator.deallocate(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.shrink(ptr, old_layout, new_layout)
+    }
+}
+
+unsafe extern "C" {
+    fn zig_c_allocator_create(
+        config: *const RawCConfig,
+        out_allocator: *mut *mut std::ffi::c_void,
+    ) -> CreateStatus;
+}
@@ -0,0 +1,27 @@
+use crate::ZigCAllocator;
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::LazyLock;
+
+/// A global allocator wrapper around ZigCAllocator
+///
+/// This provides a global allocator implementation using Zig's C allocator,
+/// over libc `malloc`. Mainly a baseline for comparisons.
+pub struct ZigGlobalCAllocator;
+
+static ALLOCATOR: LazyLock<ZigCAllocator> = LazyLock::new(ZigCAllocator::new);
+
+unsafe impl GlobalAlloc for ZigGlobalCAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { ALLOCATOR.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe {
+            ALLOCATOR.dealloc(ptr, layout);
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { ALLOCATOR.realloc(ptr, layout, new_size) }
+    }
+}
@@ -1,26 +1,87 @@
-use crate::ffi::FfiAllocator;
+use crate::event::{DebugEvent, DebugEventCallback, EventHandler, dispatch_event};
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::leak::{
+    AllocationCheckpoint, LeakCheckError, LeakReport, LiveAllocation, RawLiveAllocation,
+    collect_allocations,
+};
+use crate::zig_allocator::{ZigAllocator, sealed};
+use crate::{CreateError, ZigPageAllocator};
 use std::alloc::GlobalAlloc;
 
 /// Zig Debug Allocator
-pub struct ZigDebugAllocator {
-    /// Inner allocator
+///
+/// Requests its pages from a [`ZigPageAllocator`] unless built with [`Self::with_backing`].
+pub struct ZigDebugAllocator<B: ZigAllocator = ZigPageAllocator> {
+    /// Inner allocator, dropped before `backing`
     ffi_allocator: FfiAllocator,
+
+    /// Closure receiving memory errors, dropped after `ffi_allocator` reported the leaks
+    event_handler: Option<Box<EventHandler>>,
+
+    /// Allocator the pages are requested from
+    backing: B,
 }
 
+/// Stack trace depths compiled into the Zig debug allocator
+///
+/// Requested depths are rounded up to the nearest entry.
+pub const SUPPORTED_STACK_TRACE_FRAMES: [u8; 4] = [0, 6, 16, 32];
+
 /// Configuration for creating debug allocators
-#[repr(C, align(1))]
+#[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct DebugAllocatorConfig {
     /// Whether to panic when leaks are detected on deinit
+    ///
+    /// Ignored once an event handler is set, see [`ZigDebugAllocator::set_event_handler`].
     pub panic_on_leaks: bool,
+
+    /// Number of stack frames captured per allocation
+    ///
+    /// Rounded up to the nearest entry of [`SUPPORTED_STACK_TRACE_FRAMES`],
+    /// deeper traces are rejected with [`CreateError::InvalidConfig`].
+    pub stack_trace_frames: u8,
+
+    /// Never return freed pages to the backing allocator
+    ///
+    /// Use-after-free then hits still-mapped memory instead of segfaulting,
+    /// which keeps the Zig-side checks able to report it.
+    pub never_unmap: bool,
+
+    /// Keep metadata of freed allocations
+    ///
+    /// Double frees are then reported with the stack trace of the first free.
+    pub retain_metadata: bool,
+
+    /// Log the size and address of every allocation
+    pub verbose_log: bool,
+
+    /// Whether the allocator may be used from several threads at once
+    ///
+    /// Disabling it skips locking, which is only sound if the allocator is
+    /// never shared across threads, see [`ZigDebugAllocator::try_with_config_unchecked`].
+    pub thread_safe: bool,
+
+    /// Maximum number of bytes that may be requested at once
+    ///
+    /// Allocations that would go past it fail. `None` disables the limit.
+    pub memory_limit: Option<std::num::NonZeroUsize>,
 }
 
 // Compile-time checks to ensure FFI compatibility
 const _: () = {
     // Verify struct size and alignment match expected C layout
     assert!(
-        std::mem::size_of::<DebugAllocatorConfig>() == 1,
-        "DebugAllocatorConfig size must be 1 byte for FFI compatibility"
+        std::mem::size_of::<DebugAllocatorConfig>() == 8 + std::mem::size_of::<usize>(),
+        "DebugAllocatorConfig size must be 8 bytes plus a usize for FFI compatibility"
+    );
+    assert!(
+        std::mem::align_of::<DebugAllocatorConfig>() == std::mem::align_of::<usize>(),
+        "DebugAllocatorConfig must be aligned like a usize for FFI compatibility"
+    );
+    assert!(
+        std::mem::offset_of!(DebugAllocatorConfig, memory_limit) == 8,
+        "DebugAllocatorConfig::memory_limit must be at offset 8 for FFI compatibility"
     );
 };
 
@@ -28,31 +89,276 @@ impl Default for DebugAllocatorConfig {
     fn default() -> Self {
         Self {
             panic_on_leaks: true,
+            stack_trace_frames: 6,
+            never_unmap: false,
+            retain_metadata: false,
+            verbose_log: false,
+            thread_safe: true,
+            memory_limit: None,
         }
     }
 }
 
 impl ZigDebugAllocator {
     /// Create a new debug allocator with default settings (panics on leaks)
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new`]
     #[must_use]
     pub fn new() -> Self {
         Self::with_config(DebugAllocatorConfig::default())
     }
 
+    /// Create a new debug allocator with default settings, reporting why creation failed
+    pub fn try_new() -> Result<Self, CreateError> {
+        Self::try_with_config(DebugAllocatorConfig::default())
+    }
+
     /// Create a new debug allocator with the given configuration
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_with_config`]
     #[must_use]
     pub fn with_config(config: DebugAllocatorConfig) -> Self {
-        let allocator_ptr = unsafe { zig_debug_allocator_create(&config) };
+        Self::try_with_config(config).expect("failed to create Zig debug allocator")
+    }
 
-        Self {
-            ffi_allocator: FfiAllocator::new(allocator_ptr),
+    /// Create a new debug allocator with the given configuration, reporting why creation failed
+    ///
+    /// Configurations with `thread_safe` disabled are rejected with
+    /// [`CreateError::InvalidConfig`], use [`Self::try_with_config_unchecked`] for those.
+    pub fn try_with_config(config: DebugAllocatorConfig) -> Result<Self, CreateError> {
+        if !config.thread_safe {
+            return Err(CreateError::InvalidConfig);
         }
+
+        unsafe { Self::try_with_config_unchecked(config) }
+    }
+
+    /// Create a new debug allocator with the given configuration, allowing `thread_safe` to be disabled
+    ///
+    /// # Safety
+    ///
+    /// If `config.thread_safe` is false, the allocator must never be used from
+    /// more than one thread at a time.
+    pub unsafe fn try_with_config_unchecked(
+        config: DebugAllocatorConfig,
+    ) -> Result<Self, CreateError> {
+        unsafe { Self::try_create(ZigPageAllocator::try_new()?, config) }
     }
 
     /// Create a new debug allocator with configurable panic behavior
     #[must_use]
     pub fn with_panic_on_leaks(panic_on_leaks: bool) -> Self {
-        Self::with_config(DebugAllocatorConfig { panic_on_leaks })
+        Self::with_config(DebugAllocatorConfig {
+            panic_on_leaks,
+            ..DebugAllocatorConfig::default()
+        })
+    }
+}
+
+impl<B: ZigAllocator> ZigDebugAllocator<B> {
+    /// Create a new debug allocator requesting its pages from `backing`
+    ///
+    /// Pass a reference to keep using the backing allocator once the debug
+    /// allocator is dropped.
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_with_backing`]
+    #[must_use]
+    pub fn with_backing(backing: B, config: DebugAllocatorConfig) -> Self {
+        Self::try_with_backing(backing, config).expect("failed to create Zig debug allocator")
+    }
+
+    /// Create a new debug allocator requesting its pages from `backing`, reporting why creation failed
+    ///
+    /// Leaks and memory errors are checked on top of `backing`, for example a
+    /// production allocator exposed through [`crate::ZigExternalAllocator`].
+    /// Configurations with `thread_safe` disabled are rejected with
+    /// [`CreateError::InvalidConfig`].
+    pub fn try_with_backing(backing: B, config: DebugAllocatorConfig) -> Result<Self, CreateError> {
+        if !config.thread_safe {
+            return Err(CreateError::InvalidConfig);
+        }
+
+        unsafe { Self::try_create(backing, config) }
+    }
+
+    /// Create the Zig debug allocator over `backing`
+    ///
+    /// # Safety
+    ///
+    /// If `config.thread_safe` is false, the allocator must never be used from
+    /// more than one thread at a time.
+    unsafe fn try_create(backing: B, config: DebugAllocatorConfig) -> Result<Self, CreateError> {
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe {
+            zig_debug_allocator_create_with_backing(
+                &config,
+                backing.ffi_handle(),
+                &mut allocator_ptr,
+            )
+        };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            event_handler: None,
+            backing,
+        })
+    }
+
+    /// Allocator the pages are requested from
+    #[must_use]
+    pub fn backing(&self) -> &B {
+        &self.backing
+    }
+
+    /// Call `handler` with every memory error instead of panicking or logging
+    ///
+    /// Leaks are reported when the allocator is dropped, double frees and
+    /// invalid frees when the faulty memory is freed or resized. The handler
+    /// runs on the thread that made the faulty call, without any allocator
+    /// lock held. Faulty calls aren't passed on to the Zig allocator: frees
+    /// with the wrong layout still release the allocation, frees of memory
+    /// never handed out are ignored and resizes fail.
+    ///
+    /// Allocators must not unwind, so a panic of the handler is caught and
+    /// kept for [`Self::take_handler_panic`]. Panics raised while the
+    /// allocator is dropped are only printed by the panic hook.
+    pub fn set_event_handler(&mut self, handler: impl Fn(&DebugEvent) + Send + Sync + 'static) {
+        let handler = Box::new(EventHandler::new(handler));
+        let context = std::ptr::from_ref(&*handler).cast_mut().cast();
+
+        unsafe { self.set_event_callback(Some(dispatch_event), context) };
+
+        // The previous handler is no longer registered, it can go
+        self.event_handler = Some(handler);
+    }
+
+    /// Call `callback` with every memory error instead of panicking or logging,
+    /// or restore the default behavior if `None`
+    ///
+    /// See [`Self::set_event_handler`] for a safe alternative.
+    ///
+    /// # Safety
+    ///
+    /// `callback` must not unwind, and must be callable from any thread using
+    /// the allocator. `context` is passed to it as-is, it must stay valid for
+    /// as long as the callback is registered, which includes dropping the allocator.
+    pub unsafe fn set_event_callback(
+        &self,
+        callback: Option<DebugEventCallback>,
+        context: *mut std::ffi::c_void,
+    ) {
+        unsafe {
+            zig_debug_allocator_set_event_callback(self.ffi_allocator.as_ptr(), callback, context);
+        }
+    }
+
+    /// Take the first panic the event handler raised since the last call
+    ///
+    /// Pass it to [`std::panic::resume_unwind`] to fail the caller, as in:
+    ///
+    /// ```no_run
+    /// # let allocator = zigalloc::ZigDebugAllocator::new();
+    /// if let Some(payload) = allocator.take_handler_panic() {
+    ///     std::panic::resume_unwind(payload);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn take_handler_panic(&self) -> Option<Box<dyn std::any::Any + Send>> {
+        self.event_handler.as_ref()?.take_panic()
+    }
+
+    /// Limit the number of bytes that may be requested at once
+    ///
+    /// Allocations that would go past `bytes` then fail with a null pointer,
+    /// which makes out-of-memory handling testable. Memory already handed out
+    /// is left untouched, even if it exceeds the new limit.
+    pub fn set_memory_limit(&self, bytes: usize) {
+        unsafe { zig_debug_allocator_set_memory_limit(self.ffi_allocator.as_ptr(), bytes) }
+    }
+
+    /// Remove the memory limit, see [`Self::set_memory_limit`]
+    pub fn clear_memory_limit(&self) {
+        self.set_memory_limit(usize::MAX);
+    }
+
+    /// Number of bytes currently requested and not freed yet
+    #[must_use]
+    pub fn total_requested_bytes(&self) -> usize {
+        unsafe { zig_debug_allocator_total_requested_bytes(self.ffi_allocator.as_ptr()) }
+    }
+
+    /// Mark the current point in time, to later list what was allocated after it
+    #[must_use]
+    pub fn checkpoint(&self) -> AllocationCheckpoint {
+        AllocationCheckpoint(unsafe { zig_debug_allocator_checkpoint(self.ffi_allocator.as_ptr()) })
+    }
+
+    /// List every allocation that hasn't been freed yet, oldest first
+    ///
+    /// Fails with [`LeakCheckError::OutOfMemory`] if the Zig side can't
+    /// allocate the list.
+    pub fn live_allocations(&self) -> Result<Vec<LiveAllocation>, LeakCheckError> {
+        self.live_allocations_after(0)
+    }
+
+    /// List the allocations made after `checkpoint` that haven't been freed yet, oldest first
+    ///
+    /// `checkpoint` must come from this allocator.
+    pub fn live_allocations_since(
+        &self,
+        checkpoint: AllocationCheckpoint,
+    ) -> Result<Vec<LiveAllocation>, LeakCheckError> {
+        self.live_allocations_after(checkpoint.0)
+    }
+
+    /// Check that every allocation has been freed, without destroying the allocator
+    pub fn check_leaks(&self) -> Result<(), LeakCheckError> {
+        LeakReport::check(self.live_allocations()?)
+    }
+
+    /// Check that every allocation made after `checkpoint` has been freed
+    ///
+    /// Useful at the end of a request in long-running services, to assert
+    /// nothing leaked since the checkpoint taken at its start.
+    pub fn check_leaks_since(
+        &self,
+        checkpoint: AllocationCheckpoint,
+    ) -> Result<(), LeakCheckError> {
+        LeakReport::check(self.live_allocations_since(checkpoint)?)
+    }
+
+    /// Exclude a live allocation from leak checks
+    ///
+    /// For memory meant to live until the process exits. Returns `false`
+    /// if `ptr` isn't a live allocation of this allocator.
+    pub fn mark_static(&self, ptr: *const u8) -> bool {
+        unsafe { zig_debug_allocator_mark_static(self.ffi_allocator.as_ptr(), ptr as usize) }
+    }
+
+    fn live_allocations_after(&self, since: u64) -> Result<Vec<LiveAllocation>, LeakCheckError> {
+        collect_allocations(|out_allocations, out_len| unsafe {
+            zig_debug_allocator_live_allocations(
+                self.ffi_allocator.as_ptr(),
+                since,
+                out_allocations,
+                out_len,
+            )
+        })
+    }
+
+    /// Destroy the allocator, returning the allocations that were never freed
+    ///
+    /// Leaks returned this way are not reported by the Zig side, regardless
+    /// of `panic_on_leaks`. If they can't be listed, the Zig side reports
+    /// them as on drop and [`LeakCheckError::OutOfMemory`] is returned.
+    pub fn finish(self) -> Result<(), LeakCheckError> {
+        let allocations = collect_allocations(|out_allocations, out_len| unsafe {
+            zig_debug_allocator_take_leaks(self.ffi_allocator.as_ptr(), out_allocations, out_len)
+        });
+
+        drop(self);
+
+        LeakReport::check(allocations?)
     }
 }
 
@@ -62,7 +368,16 @@ impl Default for ZigDebugAllocator {
     }
 }
 
-unsafe impl GlobalAlloc for ZigDebugAllocator {
+impl<B: ZigAllocator> sealed::Sealed for ZigDebugAllocator<B> {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.ffi_allocator.as_ptr()
+    }
+}
+
+impl<B: ZigAllocator> ZigAllocator for ZigDebugAllocator<B> {}
+
+unsafe impl<B: ZigAllocator> GlobalAlloc for ZigDebugAllocator<B> {
     #[inline]
     unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
         self.ffi_allocator.alloc(layout)
@@ -72,10 +387,17 @@ unsafe impl GlobalAlloc for ZigDebugAllocator {
     unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
         self.ffi_allocator.dealloc(ptr, layout);
     }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let new_layout =
+            unsafe { std::alloc::Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.ffi_allocator.realloc(ptr, layout, new_layout)
+    }
 }
 
 #[cfg(feature = "nightly")]
-unsafe impl std::alloc::Allocator for ZigDebugAllocator {
+unsafe impl<B: ZigAllocator> std::alloc::Allocator for ZigDebugAllocator<B> {
     #[inline]
     fn allocate(
         &self,
@@ -88,8 +410,69 @@ unsafe impl std::alloc::Allocator for ZigDebugAllocator {
     unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
         self.ffi_allocator.deallocate(ptr, layout);
     }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.shrink(ptr, old_layout, new_layout)
+    }
 }
 
 unsafe extern "C" {
-    fn zig_debug_allocator_create(config: *const DebugAllocatorConfig) -> *mut std::ffi::c_void;
+    fn zig_debug_allocator_create_with_backing(
+        config: *const DebugAllocatorConfig,
+        backing_allocator: *mut std::ffi::c_void,
+        out_allocator: *mut *mut std::ffi::c_void,
+    ) -> CreateStatus;
+
+    fn zig_debug_allocator_set_event_callback(
+        allocator: *mut std::ffi::c_void,
+        callback: Option<DebugEventCallback>,
+        context: *mut std::ffi::c_void,
+    );
+
+    fn zig_debug_allocator_set_memory_limit(allocator: *mut std::ffi::c_void, limit: usize);
+
+    fn zig_debug_allocator_total_requested_bytes(allocator: *mut std::ffi::c_void) -> usize;
+
+    fn zig_debug_allocator_checkpoint(allocator: *mut std::ffi::c_void) -> u64;
+
+    fn zig_debug_allocator_live_allocations(
+        allocator: *mut std::ffi::c_void,
+        since: u64,
+        out_allocations: *mut *mut RawLiveAllocation,
+        out_len: *mut usize,
+    ) -> bool;
+
+    fn zig_debug_allocator_mark_static(allocator: *mut std::ffi::c_void, address: usize) -> bool;
+
+    fn zig_debug_allocator_take_leaks(
+        allocator: *mut std::ffi::c_void,
+        out_allocations: *mut *mut RawLiveAllocation,
+        out_len: *mut usize,
+    ) -> bool;
 }
@@ -1,14 +1,141 @@
-use std::{alloc::GlobalAlloc, sync::LazyLock};
+use std::{
+    alloc::GlobalAlloc,
+    cell::Cell,
+    sync::{
+        LazyLock, Once,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
-use crate::ZigDebugAllocator;
+use crate::{
+    AllocationCheckpoint, LeakCheckError, LiveAllocation, SymbolizeOptions, SymbolizedFrame,
+    ZigDebugAllocator,
+};
 
 pub struct ZigGlobalDebugAllocator;
 
+/// Allocations up to this checkpoint are ignored by [`ZigGlobalDebugAllocator::report_leaks`]
+static BASELINE: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Whether this thread is inside [`ZigGlobalDebugAllocator::static_scope`]
+    static IN_STATIC_SCOPE: Cell<bool> = const { Cell::new(false) };
+}
+
+impl ZigGlobalDebugAllocator {
+    /// Ignore every allocation made so far in later leak reports
+    ///
+    /// Call it at the start of `main`, so memory set up by the runtime
+    /// before `main` isn't reported as leaked.
+    pub fn mark_baseline() {
+        let checkpoint = get_or_init_alloc().checkpoint();
+        BASELINE.store(checkpoint.0, Ordering::Release);
+    }
+
+    /// Check that every allocation made after the baseline has been freed
+    ///
+    /// See [`Self::mark_baseline`] and [`Self::static_scope`] for what is left out.
+    pub fn report_leaks() -> Result<(), LeakCheckError> {
+        let baseline = AllocationCheckpoint(BASELINE.load(Ordering::Acquire));
+        get_or_init_alloc().check_leaks_since(baseline)
+    }
+
+    /// Run [`Self::report_leaks`] when the process exits
+    ///
+    /// Marks the baseline if none was marked yet. Allocations the standard
+    /// library makes for its own statics, like the stdout buffer or
+    /// thread-local destructors, are left out: those only reached through
+    /// `std` frames. When other leaks are found, the report is printed to
+    /// stderr with symbolized stack traces and the process exits with status 1.
+    pub fn install_exit_hook() {
+        static INSTALL: Once = Once::new();
+
+        INSTALL.call_once(|| {
+            if BASELINE.load(Ordering::Acquire) == 0 {
+                Self::mark_baseline();
+            }
+
+            let status = unsafe { libc::atexit(report_leaks_at_exit) };
+            assert_eq!(status, 0, "failed to register the leak check exit hook");
+        });
+    }
+
+    /// Run `f`, leaving out of leak reports what it allocates on this thread
+    ///
+    /// Meant for values living until the process exits, like lazy statics:
+    ///
+    /// ```ignore
+    /// static CONFIG: LazyLock<Config> =
+    ///     LazyLock::new(|| ZigGlobalDebugAllocator::static_scope(Config::load));
+    /// ```
+    ///
+    /// Statics initialized after [`Self::mark_baseline`] are not detected,
+    /// each initializer has to be wrapped by hand. Allocations made within the
+    /// scope stay out of reports when they are reallocated later on, even
+    /// outside of it.
+    pub fn static_scope<R>(f: impl FnOnce() -> R) -> R {
+        struct Restore(bool);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                IN_STATIC_SCOPE.set(self.0);
+            }
+        }
+
+        let _restore = Restore(IN_STATIC_SCOPE.replace(true));
+        f()
+    }
+}
+
+/// Exit hook registered by [`ZigGlobalDebugAllocator::install_exit_hook`]
+extern "C" fn report_leaks_at_exit() {
+    let mut report = match ZigGlobalDebugAllocator::report_leaks() {
+        Ok(()) => return,
+        Err(LeakCheckError::Leaked(report)) => report,
+        Err(err) => {
+            eprintln!("{err}");
+            return;
+        }
+    };
+
+    report.retain(|allocation| !is_runtime_static(allocation));
+    if report.allocations().is_empty() {
+        return;
+    }
+
+    let options = SymbolizeOptions {
+        skip_allocator_frames: true,
+    };
+    eprint!("{}", report.symbolized(options));
+    unsafe { libc::_exit(1) };
+}
+
+/// Whether the standard library made `allocation` for its own statics
+///
+/// Every frame past the allocator ones is then in `std`. Traces too short
+/// to reach past the allocator frames are kept.
+fn is_runtime_static(allocation: &LiveAllocation) -> bool {
+    let frames = allocation.symbolize(SymbolizeOptions {
+        skip_allocator_frames: true,
+    });
+    !frames.is_empty() && frames.iter().all(SymbolizedFrame::is_runtime)
+}
+
+/// Keep `ptr` out of leak reports if it was allocated within a static scope
+#[inline]
+fn mark_if_static(allocator: &ZigDebugAllocator, ptr: *mut u8) {
+    if !ptr.is_null() && IN_STATIC_SCOPE.try_with(Cell::get).unwrap_or(false) {
+        allocator.mark_static(ptr);
+    }
+}
+
 unsafe impl GlobalAlloc for ZigGlobalDebugAllocator {
     #[inline]
     unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
         let allocator = get_or_init_alloc();
-        unsafe { allocator.alloc(layout) }
+        let ptr = unsafe { allocator.alloc(layout) };
+        mark_if_static(allocator, ptr);
+        ptr
     }
 
     #[inline]
@@ -16,6 +143,14 @@ unsafe impl GlobalAlloc for ZigGlobalDebugAllocator {
         let allocator = get_or_init_alloc();
         unsafe { allocator.dealloc(ptr, layout) }
     }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let allocator = get_or_init_alloc();
+        let new_ptr = unsafe { allocator.realloc(ptr, layout, new_size) };
+        mark_if_static(allocator, new_ptr);
+        new_ptr
+    }
 }
 
 #[inline]
@@ -0,0 +1,25 @@
+/// Error returned when a Zig allocator cannot be created
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CreateError {
+    /// Not enough memory to set up the allocator
+    OutOfMemory,
+    /// The configuration was rejected by the Zig side
+    InvalidConfig,
+    /// The Zig side returned a status this crate doesn't know about
+    UnknownStatus(std::ffi::c_int),
+}
+
+impl std::fmt::Display for CreateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfMemory => f.write_str("out of memory while creating Zig allocator"),
+            Self::InvalidConfig => f.write_str("invalid Zig allocator configuration"),
+            Self::UnknownStatus(status) => {
+                write!(f, "unknown status {status} while creating Zig allocator")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CreateError {}
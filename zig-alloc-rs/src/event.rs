@@ -0,0 +1,204 @@
+use std::any::Any;
+use std::ffi::{c_int, c_void};
+use std::sync::Mutex;
+
+use crate::leak::{MAX_STACK_TRACE_FRAMES, RawLiveAllocation};
+use crate::{LiveAllocation, SymbolizeOptions, SymbolizedFrame, symbolize};
+
+/// Function called by the Zig debug allocator with every memory error
+///
+/// `event` is only valid for the duration of the call.
+pub type DebugEventCallback = unsafe extern "C" fn(event: *const DebugEvent, context: *mut c_void);
+
+/// Kind of memory error reported by the debug allocator
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DebugEventKind {
+    /// Allocation never freed, reported when the allocator is dropped
+    Leak,
+    /// Allocation freed twice
+    DoubleFree,
+    /// Free or resize of an allocation with the wrong layout, or of memory
+    /// that was never handed out
+    InvalidFree,
+    /// Kind reported by the Zig side this crate doesn't know about
+    Unknown(c_int),
+}
+
+impl std::fmt::Display for DebugEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Leak => f.write_str("leak"),
+            Self::DoubleFree => f.write_str("double free"),
+            Self::InvalidFree => f.write_str("invalid free"),
+            Self::Unknown(kind) => write!(f, "unknown event kind {kind}"),
+        }
+    }
+}
+
+/// Memory error reported by the debug allocator (`Event` in Zig)
+#[repr(C)]
+pub struct DebugEvent {
+    /// What went wrong, `EventKind` in Zig
+    kind: c_int,
+    /// Allocation concerned, zeroed if the address was never handed out
+    allocation: RawLiveAllocation,
+    /// Address passed by the faulty call
+    address: usize,
+    /// Size passed by the faulty call
+    size: usize,
+    /// Alignment passed by the faulty call
+    alignment: usize,
+    /// Number of valid entries in `trace`
+    trace_len: usize,
+    /// Return addresses leading to the faulty call
+    trace: [usize; MAX_STACK_TRACE_FRAMES],
+}
+
+// Compile-time checks to ensure FFI compatibility
+const _: () = {
+    assert!(
+        std::mem::size_of::<DebugEvent>()
+            == std::mem::align_of::<RawLiveAllocation>()
+                + std::mem::size_of::<RawLiveAllocation>()
+                + (4 + MAX_STACK_TRACE_FRAMES) * std::mem::size_of::<usize>(),
+        "DebugEvent size must match the Zig Event for FFI compatibility"
+    );
+};
+
+impl DebugEvent {
+    /// What went wrong
+    #[must_use]
+    pub fn kind(&self) -> DebugEventKind {
+        match self.kind {
+            0 => DebugEventKind::Leak,
+            1 => DebugEventKind::DoubleFree,
+            2 => DebugEventKind::InvalidFree,
+            kind => DebugEventKind::Unknown(kind),
+        }
+    }
+
+    /// Allocation concerned, `None` if the address was never handed out
+    ///
+    /// For double frees, this is the allocation as it was before the first free.
+    #[must_use]
+    pub fn allocation(&self) -> Option<LiveAllocation> {
+        let allocation = LiveAllocation::from(&self.allocation);
+        (allocation.address != 0).then_some(allocation)
+    }
+
+    /// Address passed by the faulty call, or of the leaked allocation
+    #[must_use]
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Size in bytes passed by the faulty call, or of the leaked allocation
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Alignment in bytes passed by the faulty call, or of the leaked allocation
+    #[must_use]
+    pub fn align(&self) -> usize {
+        self.alignment
+    }
+
+    /// Return addresses leading to the faulty call, innermost first
+    ///
+    /// Empty for leaks, see [`Self::allocation`] for where they were allocated.
+    #[must_use]
+    pub fn return_addresses(&self) -> &[usize] {
+        &self.trace[..self.trace_len.min(MAX_STACK_TRACE_FRAMES)]
+    }
+
+    /// Resolve the stack trace leading to the faulty call, innermost first
+    #[must_use]
+    pub fn symbolize(&self, options: SymbolizeOptions) -> Vec<SymbolizedFrame> {
+        symbolize(self.return_addresses(), options)
+    }
+}
+
+impl std::fmt::Debug for DebugEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DebugEvent")
+            .field("kind", &self.kind())
+            .field("allocation", &self.allocation())
+            .field("address", &self.address)
+            .field("size", &self.size)
+            .field("align", &self.alignment)
+            .field("return_addresses", &self.return_addresses())
+            .finish()
+    }
+}
+
+impl std::fmt::Display for DebugEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} byte(s) at {:#x}",
+            self.kind(),
+            self.size,
+            self.address
+        )?;
+
+        if let Some(allocation) = self.allocation()
+            && (allocation.size != self.size || allocation.align != self.alignment)
+        {
+            write!(
+                f,
+                " (allocated as {} byte(s), align {})",
+                allocation.size, allocation.align
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Rust closure registered with [`crate::ZigDebugAllocator::set_event_handler`]
+pub(crate) struct EventHandler {
+    /// Closure to call
+    handler: Box<dyn Fn(&DebugEvent) + Send + Sync>,
+
+    /// First panic raised by the closure, waiting to be taken
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl EventHandler {
+    pub(crate) fn new(handler: impl Fn(&DebugEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            handler: Box::new(handler),
+            panic: Mutex::new(None),
+        }
+    }
+
+    /// Take the first panic raised by the closure since the last call
+    pub(crate) fn take_panic(&self) -> Option<Box<dyn Any + Send>> {
+        self.panic
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take()
+    }
+}
+
+/// Call the [`EventHandler`] passed as context
+///
+/// Panics can't unwind through Zig, nor out of an allocator, they are caught
+/// and kept until [`EventHandler::take_panic`].
+pub(crate) unsafe extern "C" fn dispatch_event(event: *const DebugEvent, context: *mut c_void) {
+    let handler = unsafe { &*context.cast::<EventHandler>() };
+    let event = unsafe { &*event };
+
+    let result =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| (handler.handler)(event)));
+    if let Err(payload) = result {
+        let mut panic = handler
+            .panic
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // Keep the first panic if the handler panicked several times
+        panic.get_or_insert(payload);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::CreateError;
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::zig_allocator::{ZigAllocator, sealed};
+use std::alloc::{GlobalAlloc, Layout};
+use std::ffi::c_void;
+
+/// Rust allocator exposed to Zig, to back the other allocators of this crate
+///
+/// Zig sees `A` as a `std.mem.Allocator`, so for example an arena or a debug
+/// allocator can request its memory from [`std::alloc::System`] or any other
+/// [`GlobalAlloc`]:
+///
+/// ```no_run
+/// use std::alloc::System;
+/// use zigalloc::{ZigArenaAllocator, ZigExternalAllocator};
+///
+/// let arena = ZigArenaAllocator::with_backing(ZigExternalAllocator::new(System));
+/// ```
+///
+/// Zig never resizes this memory in place, growing and shrinking go through
+/// [`GlobalAlloc::realloc`]. A panic in `A` aborts the process, since it can't
+/// unwind through Zig.
+pub struct ZigExternalAllocator<A: GlobalAlloc> {
+    /// Inner allocator, dropped before `allocator`
+    ffi_allocator: FfiAllocator,
+
+    /// Rust allocator, boxed so the context handed to Zig never moves
+    allocator: Box<A>,
+}
+
+/// [`GlobalAlloc`] view of a [`std::alloc::Allocator`], to hand it to [`ZigExternalAllocator`]
+#[cfg(feature = "nightly")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllocatorAdapter<A: std::alloc::Allocator>(pub A);
+
+/// Configuration passed to the Zig side (`ExternalAllocator.Config`)
+#[repr(C)]
+struct RawExternalConfig {
+    /// Pointer to the Rust allocator
+    context: *mut c_void,
+    /// Allocate `len` bytes aligned to `alignment`
+    alloc: unsafe extern "C" fn(*mut c_void, usize, usize) -> *mut u8,
+    /// Move memory to a block of a new length
+    realloc: unsafe extern "C" fn(*mut c_void, *mut u8, usize, usize, usize) -> *mut u8,
+    /// Free memory of the given length and alignment
+    free: unsafe extern "C" fn(*mut c_void, *mut u8, usize, usize),
+}
+
+impl<A: GlobalAlloc> ZigExternalAllocator<A> {
+    /// Expose `allocator` to Zig
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new`]
+    #[must_use]
+    pub fn new(allocator: A) -> Self {
+        Self::try_new(allocator).expect("failed to create Zig external allocator")
+    }
+
+    /// Expose `allocator` to Zig, reporting why creation failed
+    pub fn try_new(allocator: A) -> Result<Self, CreateError> {
+        let allocator = Box::new(allocator);
+        let config = RawExternalConfig {
+            context: std::ptr::from_ref(&*allocator).cast_mut().cast(),
+            alloc: external_alloc::<A>,
+            realloc: external_realloc::<A>,
+            free: external_free::<A>,
+        };
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe { zig_external_allocator_create(&config, &mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            allocator,
+        })
+    }
+
+    /// Rust allocator serving the memory
+    #[must_use]
+    pub fn inner(&self) -> &A {
+        &self.allocator
+    }
+}
+
+/// Well-aligned, non-null pointer standing for a zero-sized block
+#[inline]
+fn dangling(alignment: usize) -> *mut u8 {
+    std::ptr::without_provenance_mut(alignment)
+}
+
+/// `ExternalAllocator.alloc_fn` calling `A`
+unsafe extern "C" fn external_alloc<A: GlobalAlloc>(
+    context: *mut c_void,
+    len: usize,
+    alignment: usize,
+) -> *mut u8 {
+    let Ok(layout) = Layout::from_size_align(len, alignment) else {
+        return std::ptr::null_mut();
+    };
+
+    // `GlobalAlloc` doesn't take zero-sized layouts
+    if len == 0 {
+        return dangling(alignment);
+    }
+
+    let allocator = unsafe { &*context.cast::<A>() };
+    unsafe { allocator.alloc(layout) }
+}
+
+/// `ExternalAllocator.realloc_fn` calling `A`
+unsafe extern "C" fn external_realloc<A: GlobalAlloc>(
+    context: *mut c_void,
+    memory: *mut u8,
+    len: usize,
+    alignment: usize,
+    new_len: usize,
+) -> *mut u8 {
+    if len == 0 || new_len == 0 || Layout::from_size_align(new_len, alignment).is_err() {
+        let new_memory = unsafe { external_alloc::<A>(context, new_len, alignment) };
+        if !new_memory.is_null() {
+            unsafe {
+                new_memory.copy_from_nonoverlapping(memory, len.min(new_len));
+                external_free::<A>(context, memory, len, alignment);
+            }
+        }
+        return new_memory;
+    }
+
+    let allocator = unsafe { &*context.cast::<A>() };
+    let layout = unsafe { Layout::from_size_align_unchecked(len, alignment) };
+    unsafe { allocator.realloc(memory, layout, new_len) }
+}
+
+/// `ExternalAllocator.free_fn` calling `A`
+unsafe extern "C" fn external_free<A: GlobalAlloc>(
+    context: *mut c_void,
+    memory: *mut u8,
+    len: usize,
+    alignment: usize,
+) {
+    if len == 0 {
+        return;
+    }
+
+    let allocator = unsafe { &*context.cast::<A>() };
+    let layout = unsafe { Layout::from_size_align_unchecked(len, alignment) };
+    unsafe { allocator.dealloc(memory, layout) };
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl<A: std::alloc::Allocator> GlobalAlloc for AllocatorAdapter<A> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.0
+            .allocate(layout)
+            .map_or(std::ptr::null_mut(), |ptr| ptr.as_ptr().cast())
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe {
+            self.0
+                .deallocate(std::ptr::NonNull::new_unchecked(ptr), layout)
+        };
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let ptr = unsafe { std::ptr::NonNull::new_unchecked(ptr) };
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+
+        let result = if new_size >= layout.size() {
+            unsafe { self.0.grow(ptr, layout, new_layout) }
+        } else {
+            unsafe { self.0.shrink(ptr, layout, new_layout) }
+        };
+        result.map_or(std::ptr::null_mut(), |ptr| ptr.as_ptr().cast())
+    }
+}
+
+impl<A: GlobalAlloc> sealed::Sealed for ZigExternalAllocator<A> {
+    #[inline]
+    fn ffi_handle(&self) -> *mut c_void {
+        self.ffi_allocator.as_ptr()
+    }
+}
+
+impl<A: GlobalAlloc> ZigAllocator for ZigExternalAllocator<A> {}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for ZigExternalAllocator<A> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.ffi_allocator.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.ffi_allocator.dealloc(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.ffi_allocator.realloc(ptr, layout, new_layout)
+    }
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl<A: GlobalAlloc> std::alloc::Allocator for ZigExternalAllocator<A> {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: Layout) {
+        self.ffi_allocator.deallocate(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.shrink(ptr, old_layout, new_layout)
+    }
+}
+
+unsafe extern "C" {
+    fn zig_external_allocator_create(
+        config: *const RawExternalConfig,
+        out_allocator: *mut *mut c_void,
+    ) -> CreateStatus;
+}
@@ -0,0 +1,225 @@
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::zig_allocator::{ZigAllocator, sealed};
+use crate::{CreateError, ZigSmpAllocator};
+use std::alloc::GlobalAlloc;
+
+/// Zig Failing Allocator, making chosen allocations fail to test out-of-memory paths
+///
+/// Wraps a [`ZigSmpAllocator`] unless built with [`Self::with_backing`]. Modeled
+/// on Zig's `std.testing.FailingAllocator`.
+pub struct ZigFailingAllocator<B: ZigAllocator = ZigSmpAllocator> {
+    /// Inner allocator, dropped before `backing`
+    ffi_allocator: FfiAllocator,
+
+    /// Allocator serving the allocations that don't fail
+    backing: B,
+}
+
+/// Which allocations a [`ZigFailingAllocator`] makes fail
+///
+/// Allocations fail as soon as one of the conditions is met.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FailingAllocatorConfig {
+    /// Index of the first allocation to fail, counting from 0
+    ///
+    /// Like `std.testing.FailingAllocator`, every allocation fails from then on.
+    pub fail_index: Option<usize>,
+
+    /// Total number of bytes that may be allocated, freed ones included
+    ///
+    /// Allocations and growths that would go past it fail.
+    pub byte_limit: Option<usize>,
+
+    /// Probability for any allocation or growth to fail, from 0 to 1
+    pub failure_probability: f64,
+
+    /// Seed of the generator drawing the random failures, which makes them reproducible
+    pub seed: u64,
+}
+
+/// Counters of a [`ZigFailingAllocator`] (`Stats` in Zig)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FailingAllocatorStats {
+    /// Successful allocations
+    pub allocations: usize,
+
+    /// Frees
+    pub deallocations: usize,
+
+    /// Bytes allocated, growths included
+    pub allocated_bytes: usize,
+
+    /// Bytes freed, shrinks included
+    pub freed_bytes: usize,
+
+    /// Allocations and growths failed on purpose
+    pub failures: usize,
+}
+
+/// Configuration passed to the Zig side (`FailingAllocator.Config`)
+#[repr(C)]
+struct RawFailingConfig {
+    /// `FfiAllocator` handle serving the allocations
+    backing_allocator: *mut std::ffi::c_void,
+    /// Index of the first allocation to fail, `usize::MAX` for none
+    fail_index: usize,
+    /// Total number of bytes that may be allocated, `usize::MAX` for no limit
+    byte_limit: usize,
+    /// Probability for any allocation or growth to fail
+    failure_probability: f64,
+    /// Seed of the generator drawing the random failures
+    seed: u64,
+}
+
+impl ZigFailingAllocator {
+    /// Create a new failing allocator over a [`ZigSmpAllocator`]
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new`]
+    #[must_use]
+    pub fn new(config: FailingAllocatorConfig) -> Self {
+        Self::try_new(config).expect("failed to create Zig failing allocator")
+    }
+
+    /// Create a new failing allocator over a [`ZigSmpAllocator`], reporting why creation failed
+    ///
+    /// Fails with [`CreateError::InvalidConfig`] if the failure probability is
+    /// not between 0 and 1.
+    pub fn try_new(config: FailingAllocatorConfig) -> Result<Self, CreateError> {
+        Self::try_with_backing(ZigSmpAllocator::try_new()?, config)
+    }
+}
+
+impl<B: ZigAllocator> ZigFailingAllocator<B> {
+    /// Create a new failing allocator over `backing`
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_with_backing`]
+    #[must_use]
+    pub fn with_backing(backing: B, config: FailingAllocatorConfig) -> Self {
+        Self::try_with_backing(backing, config).expect("failed to create Zig failing allocator")
+    }
+
+    /// Create a new failing allocator over `backing`, reporting why creation failed
+    pub fn try_with_backing(
+        backing: B,
+        config: FailingAllocatorConfig,
+    ) -> Result<Self, CreateError> {
+        let raw_config = RawFailingConfig {
+            backing_allocator: backing.ffi_handle(),
+            fail_index: config.fail_index.unwrap_or(usize::MAX),
+            byte_limit: config.byte_limit.unwrap_or(usize::MAX),
+            failure_probability: config.failure_probability,
+            seed: config.seed,
+        };
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe { zig_failing_allocator_create(&raw_config, &mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            backing,
+        })
+    }
+
+    /// Allocator serving the allocations that don't fail
+    #[must_use]
+    pub fn backing(&self) -> &B {
+        &self.backing
+    }
+
+    /// Counters of the allocations that went through so far
+    #[must_use]
+    pub fn stats(&self) -> FailingAllocatorStats {
+        let mut stats = FailingAllocatorStats::default();
+
+        let found = unsafe { zig_failing_allocator_stats(self.ffi_allocator.as_ptr(), &mut stats) };
+        assert!(found, "invalid Zig failing allocator handle");
+
+        stats
+    }
+}
+
+impl<B: ZigAllocator> sealed::Sealed for ZigFailingAllocator<B> {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.ffi_allocator.as_ptr()
+    }
+}
+
+impl<B: ZigAllocator> ZigAllocator for ZigFailingAllocator<B> {}
+
+unsafe impl<B: ZigAllocator> GlobalAlloc for ZigFailingAllocator<B> {
+    #[inline]
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        self.ffi_allocator.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        self.ffi_allocator.dealloc(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let new_layout =
+            unsafe { std::alloc::Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.ffi_allocator.realloc(ptr, layout, new_layout)
+    }
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl<B: ZigAllocator> std::alloc::Allocator for ZigFailingAllocator<B> {
+    #[inline]
+    fn allocate(
+        &self,
+        layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        self.ffi_allocator.deallocate(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.shrink(ptr, old_layout, new_layout)
+    }
+}
+
+unsafe extern "C" {
+    fn zig_failing_allocator_create(
+        config: *const RawFailingConfig,
+        out_allocator: *mut *mut std::ffi::c_void,
+    ) -> CreateStatus;
+
+    fn zig_failing_allocator_stats(
+        allocator: *mut std::ffi::c_void,
+        out_stats: *mut FailingAllocatorStats,
+    ) -> bool;
+}
@@ -1,14 +1,39 @@
+use crate::CreateError;
+
 /// FFI allocator wrapper
 pub(crate) struct FfiAllocator {
     /// Ptr
     allocator_ptr: *mut std::ffi::c_void,
 }
 
+/// Status returned by the Zig `*_create` exports (`ffi.CreateStatus`)
+pub(crate) type CreateStatus = std::ffi::c_int;
+
+/// The allocator was created
+const CREATE_STATUS_OK: CreateStatus = 0;
+/// Not enough memory to set up the allocator
+const CREATE_STATUS_OUT_OF_MEMORY: CreateStatus = 1;
+/// The provided configuration is missing or unsupported
+const CREATE_STATUS_INVALID_CONFIG: CreateStatus = 2;
+
 impl FfiAllocator {
-    /// Create a new `FfiAllocator`
-    #[must_use]
-    pub(crate) fn new(allocator_ptr: *mut std::ffi::c_void) -> Self {
-        Self { allocator_ptr }
+    /// Create a new `FfiAllocator` from the result of a Zig `*_create` export
+    pub(crate) fn from_create(
+        status: CreateStatus,
+        allocator_ptr: *mut std::ffi::c_void,
+    ) -> Result<Self, CreateError> {
+        match status {
+            CREATE_STATUS_OK if !allocator_ptr.is_null() => Ok(Self { allocator_ptr }),
+            CREATE_STATUS_OK | CREATE_STATUS_OUT_OF_MEMORY => Err(CreateError::OutOfMemory),
+            CREATE_STATUS_INVALID_CONFIG => Err(CreateError::InvalidConfig),
+            _ => Err(CreateError::UnknownStatus(status)),
+        }
+    }
+
+    /// Opaque handle passed to the Zig exports
+    #[inline]
+    pub(crate) fn as_ptr(&self) -> *mut std::ffi::c_void {
+        self.allocator_ptr
     }
 }
 
@@ -43,6 +68,10 @@ impl FfiAllocator {
         &self,
         layout: std::alloc::Layout,
     ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+
         let ptr = self.alloc(layout);
         if ptr.is_null() {
             return Err(std::alloc::AllocError);
@@ -57,6 +86,102 @@ impl FfiAllocator {
         Ok(mem)
     }
 
+    /// Reallocate some memory, resizing it in place when possible
+    ///
+    /// Returns null on failure, in which case `ptr` is left untouched.
+    #[inline]
+    pub(crate) fn realloc(
+        &self,
+        ptr: *mut u8,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> *mut u8 {
+        unsafe {
+            zig_ffi_allocator_realloc(
+                self.allocator_ptr,
+                ptr.cast::<std::ffi::c_void>(),
+                old_layout.size() as std::ffi::c_long,
+                old_layout.align() as std::ffi::c_long,
+                new_layout.size() as std::ffi::c_long,
+                new_layout.align() as std::ffi::c_long,
+            )
+        }
+    }
+
+    /// Grow some memory
+    #[cfg(feature = "nightly")]
+    #[inline]
+    pub(crate) fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.reallocate(ptr, old_layout, new_layout)
+    }
+
+    /// Grow some memory, zeroing the new bytes
+    #[cfg(feature = "nightly")]
+    #[inline]
+    pub(crate) fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        let mem = self.reallocate(ptr, old_layout, new_layout)?;
+
+        unsafe {
+            mem.cast::<u8>()
+                .add(old_layout.size())
+                .write_bytes(0, new_layout.size() - old_layout.size());
+        }
+
+        Ok(mem)
+    }
+
+    /// Shrink some memory
+    #[cfg(feature = "nightly")]
+    #[inline]
+    pub(crate) fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.reallocate(ptr, old_layout, new_layout)
+    }
+
+    /// Shared implementation of `grow` and `shrink`
+    ///
+    /// Zig allocators have no notion of zero-sized allocations, so those are
+    /// handled here rather than forwarded to `zig_ffi_allocator_realloc`.
+    #[cfg(feature = "nightly")]
+    #[inline]
+    fn reallocate(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        if old_layout.size() == 0 {
+            return self.allocate(new_layout);
+        }
+
+        if new_layout.size() == 0 {
+            self.deallocate(ptr, old_layout);
+            return Ok(dangling(new_layout));
+        }
+
+        let new_ptr = self.realloc(ptr.as_ptr(), old_layout, new_layout);
+        let non_null_ptr = std::ptr::NonNull::new(new_ptr).ok_or(std::alloc::AllocError)?;
+
+        Ok(std::ptr::NonNull::slice_from_raw_parts(
+            non_null_ptr,
+            new_layout.size(),
+        ))
+    }
+
     /// Dealloc some memory from the allocator
     #[inline]
     pub(crate) fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
@@ -74,11 +199,24 @@ impl FfiAllocator {
     #[cfg(feature = "nightly")]
     #[inline]
     pub(crate) fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+
         let ptr = unsafe { std::mem::transmute::<std::ptr::NonNull<u8>, *mut u8>(ptr) };
         self.dealloc(ptr, layout);
     }
 }
 
+/// Well-aligned, non-null pointer for a zero-sized allocation
+#[cfg(feature = "nightly")]
+#[inline]
+fn dangling(layout: std::alloc::Layout) -> std::ptr::NonNull<[u8]> {
+    let ptr = std::ptr::without_provenance_mut::<u8>(layout.align());
+    let non_null_ptr = unsafe { std::ptr::NonNull::new_unchecked(ptr) };
+    std::ptr::NonNull::slice_from_raw_parts(non_null_ptr, 0)
+}
+
 unsafe extern "C" {
     fn zig_ffi_allocator_destroy(allocator: *mut std::ffi::c_void);
 
@@ -88,6 +226,15 @@ unsafe extern "C" {
         align: std::ffi::c_long,
     ) -> *mut u8;
 
+    fn zig_ffi_allocator_realloc(
+        allocator: *mut std::ffi::c_void,
+        memory: *mut std::ffi::c_void,
+        old_size: std::ffi::c_long,
+        old_align: std::ffi::c_long,
+        new_size: std::ffi::c_long,
+        new_align: std::ffi::c_long,
+    ) -> *mut u8;
+
     fn zig_ffi_allocator_dealloc(
         allocator: *mut std::ffi::c_void,
         memory: *mut std::ffi::c_void,
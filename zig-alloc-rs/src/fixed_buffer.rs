@@ -0,0 +1,156 @@
+use crate::CreateError;
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::zig_allocator::{ZigAllocator, sealed};
+use std::alloc::GlobalAlloc;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+/// Zig Fixed Buffer Allocator, serving allocations from a caller-provided buffer
+///
+/// Never touches the heap: once the buffer is full, allocations fail. Only the
+/// most recent allocation can be freed or resized in place, [`Self::reset`]
+/// frees everything else. Not `Sync`, the allocator can't be shared across threads.
+pub struct ZigFixedBufferAllocator<'a> {
+    /// Inner allocator
+    ffi_allocator: FfiAllocator,
+
+    /// Borrow of the buffer, and makes the allocator `!Sync`
+    buffer: PhantomData<(&'a mut [MaybeUninit<u8>], Cell<()>)>,
+}
+
+/// Configuration passed to the Zig side (`FixedBufferAllocator.Config`)
+#[repr(C)]
+struct RawFixedBufferConfig {
+    /// Start of the buffer
+    buffer: *mut u8,
+    /// Length of the buffer in bytes
+    len: usize,
+}
+
+impl<'a> ZigFixedBufferAllocator<'a> {
+    /// Create a new fixed buffer allocator over `buffer`
+    ///
+    /// The buffer is taken as uninitialized, since allocations may leave
+    /// uninitialized bytes behind, such as padding.
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new`]
+    #[must_use]
+    pub fn new(buffer: &'a mut [MaybeUninit<u8>]) -> Self {
+        Self::try_new(buffer).expect("failed to create Zig fixed buffer allocator")
+    }
+
+    /// Create a new fixed buffer allocator over `buffer`, reporting why creation failed
+    pub fn try_new(buffer: &'a mut [MaybeUninit<u8>]) -> Result<Self, CreateError> {
+        let config = RawFixedBufferConfig {
+            buffer: buffer.as_mut_ptr().cast(),
+            len: buffer.len(),
+        };
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe { zig_fixed_buffer_allocator_create(&config, &mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            buffer: PhantomData,
+        })
+    }
+
+    /// Free everything allocated so far
+    ///
+    /// Taking `&mut self` guarantees no allocation is still borrowed.
+    pub fn reset(&mut self) {
+        let found = unsafe { zig_fixed_buffer_allocator_reset(self.ffi_allocator.as_ptr()) };
+        debug_assert!(found);
+    }
+
+    /// Number of bytes of the buffer used so far, alignment padding included
+    #[must_use]
+    pub fn end_index(&self) -> usize {
+        unsafe { zig_fixed_buffer_allocator_end_index(self.ffi_allocator.as_ptr()) }
+    }
+}
+
+impl sealed::Sealed for ZigFixedBufferAllocator<'_> {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.ffi_allocator.as_ptr()
+    }
+}
+
+impl ZigAllocator for ZigFixedBufferAllocator<'_> {}
+
+unsafe impl GlobalAlloc for ZigFixedBufferAllocator<'_> {
+    #[inline]
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        self.ffi_allocator.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        self.ffi_allocator.dealloc(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let new_layout =
+            unsafe { std::alloc::Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.ffi_allocator.realloc(ptr, layout, new_layout)
+    }
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl std::alloc::Allocator for ZigFixedBufferAllocator<'_> {
+    #[inline]
+    fn allocate(
+        &self,
+        layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        self.ffi_allocator.deallocate(ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.shrink(ptr, old_layout, new_layout)
+    }
+}
+
+unsafe extern "C" {
+    fn zig_fixed_buffer_allocator_create(
+        config: *const RawFixedBufferConfig,
+        out_allocator: *mut *mut std::ffi::c_void,
+    ) -> CreateStatus;
+
+    fn zig_fixed_buffer_allocator_reset(allocator: *mut std::ffi::c_void) -> bool;
+
+    fn zig_fixed_buffer_allocator_end_index(allocator: *mut std::ffi::c_void) -> usize;
+}
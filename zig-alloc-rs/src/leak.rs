@@ -0,0 +1,242 @@
+use crate::symbolize::{SymbolizeOptions, SymbolizedFrame, symbolize};
+
+/// Deepest stack trace recorded per allocation (`max_stack_trace_frames` in Zig)
+pub(crate) const MAX_STACK_TRACE_FRAMES: usize = 32;
+
+/// Allocation tracked by the debug allocator that hasn't been freed yet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveAllocation {
+    /// Increasing number identifying when the allocation was made
+    pub sequence: u64,
+
+    /// Address of the allocation
+    pub address: usize,
+
+    /// Size in bytes
+    pub size: usize,
+
+    /// Alignment in bytes
+    pub align: usize,
+
+    /// Return addresses leading to the allocation, innermost first
+    pub return_addresses: Vec<usize>,
+}
+
+impl LiveAllocation {
+    /// Resolve the stack trace leading to the allocation, innermost first
+    #[must_use]
+    pub fn symbolize(&self, options: SymbolizeOptions) -> Vec<SymbolizedFrame> {
+        symbolize(&self.return_addresses, options)
+    }
+}
+
+/// Point in time of a debug allocator, see [`crate::ZigDebugAllocator::checkpoint`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AllocationCheckpoint(pub(crate) u64);
+
+/// Allocations that were never freed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeakReport {
+    /// Leaked allocations
+    allocations: Vec<LiveAllocation>,
+}
+
+impl LeakReport {
+    /// Report the given allocations as leaks, if there are any
+    pub(crate) fn check(allocations: Vec<LiveAllocation>) -> Result<(), LeakCheckError> {
+        if allocations.is_empty() {
+            Ok(())
+        } else {
+            Err(LeakCheckError::Leaked(Self { allocations }))
+        }
+    }
+
+    /// Leaked allocations
+    #[must_use]
+    pub fn allocations(&self) -> &[LiveAllocation] {
+        &self.allocations
+    }
+
+    /// Keep only the allocations `f` returns true for
+    pub(crate) fn retain(&mut self, f: impl FnMut(&LiveAllocation) -> bool) {
+        self.allocations.retain(f);
+    }
+
+    /// Total number of leaked bytes
+    #[must_use]
+    pub fn total_bytes(&self) -> usize {
+        self.allocations
+            .iter()
+            .map(|allocation| allocation.size)
+            .sum()
+    }
+
+    /// Display the report with symbolized stack traces
+    #[must_use]
+    pub fn symbolized(&self, options: SymbolizeOptions) -> SymbolizedLeakReport<'_> {
+        SymbolizedLeakReport {
+            report: self,
+            options,
+        }
+    }
+
+    fn write_header(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} allocation(s) leaked, {} byte(s) in total",
+            self.allocations.len(),
+            self.total_bytes()
+        )
+    }
+
+    fn write_allocation(
+        f: &mut std::fmt::Formatter<'_>,
+        allocation: &LiveAllocation,
+    ) -> std::fmt::Result {
+        writeln!(
+            f,
+            "  {:#x}: {} byte(s), align {}",
+            allocation.address, allocation.size, allocation.align
+        )
+    }
+}
+
+/// [`LeakReport`] displayed with symbolized stack traces, see [`LeakReport::symbolized`]
+#[derive(Clone, Copy, Debug)]
+pub struct SymbolizedLeakReport<'a> {
+    /// Report to display
+    report: &'a LeakReport,
+
+    /// How to symbolize the stack traces
+    options: SymbolizeOptions,
+}
+
+impl std::fmt::Display for SymbolizedLeakReport<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.report.write_header(f)?;
+
+        for allocation in &self.report.allocations {
+            LeakReport::write_allocation(f, allocation)?;
+            for frame in allocation.symbolize(self.options) {
+                writeln!(f, "    at {frame}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_header(f)?;
+
+        for allocation in &self.allocations {
+            Self::write_allocation(f, allocation)?;
+            for return_address in &allocation.return_addresses {
+                writeln!(f, "    at {return_address:#x}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for LeakReport {}
+
+/// Error returned by the leak checks of a debug allocator
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeakCheckError {
+    /// Some allocations were never freed
+    Leaked(LeakReport),
+    /// Not enough memory on the Zig side to list the live allocations
+    OutOfMemory,
+}
+
+impl std::fmt::Display for LeakCheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Leaked(report) => report.fmt(f),
+            Self::OutOfMemory => {
+                f.write_str("out of memory while listing Zig debug allocator allocations")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LeakCheckError {}
+
+/// Live allocation as reported through the C ABI (`LiveAllocation` in Zig)
+#[repr(C)]
+pub(crate) struct RawLiveAllocation {
+    /// Increasing number identifying when the allocation was made
+    sequence: u64,
+    /// Address of the allocation
+    address: usize,
+    /// Size in bytes
+    size: usize,
+    /// Alignment in bytes
+    alignment: usize,
+    /// Number of valid entries in `trace`
+    trace_len: usize,
+    /// Return addresses leading to the allocation
+    trace: [usize; MAX_STACK_TRACE_FRAMES],
+}
+
+// Compile-time checks to ensure FFI compatibility
+const _: () = {
+    assert!(
+        std::mem::size_of::<RawLiveAllocation>()
+            == std::mem::size_of::<u64>()
+                + (4 + MAX_STACK_TRACE_FRAMES) * std::mem::size_of::<usize>(),
+        "RawLiveAllocation size must match the Zig LiveAllocation for FFI compatibility"
+    );
+};
+
+impl From<&RawLiveAllocation> for LiveAllocation {
+    fn from(raw: &RawLiveAllocation) -> Self {
+        let trace_len = raw.trace_len.min(MAX_STACK_TRACE_FRAMES);
+
+        Self {
+            sequence: raw.sequence,
+            address: raw.address,
+            size: raw.size,
+            align: raw.alignment,
+            return_addresses: raw.trace[..trace_len].to_vec(),
+        }
+    }
+}
+
+/// Collect the allocations listed by a `zig_debug_allocator_*` export
+///
+/// `fetch` fills in a buffer owned by Zig, which is released once copied.
+/// Allocations are returned oldest first.
+pub(crate) fn collect_allocations(
+    fetch: impl FnOnce(*mut *mut RawLiveAllocation, *mut usize) -> bool,
+) -> Result<Vec<LiveAllocation>, LeakCheckError> {
+    let mut raw_allocations = std::ptr::null_mut();
+    let mut len = 0;
+
+    if !fetch(&mut raw_allocations, &mut len) {
+        return Err(LeakCheckError::OutOfMemory);
+    }
+
+    if raw_allocations.is_null() || len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut allocations: Vec<LiveAllocation> =
+        unsafe { std::slice::from_raw_parts(raw_allocations, len) }
+            .iter()
+            .map(LiveAllocation::from)
+            .collect();
+
+    unsafe { zig_debug_allocator_free_allocations(raw_allocations, len) };
+
+    allocations.sort_unstable_by_key(|allocation| allocation.sequence);
+    Ok(allocations)
+}
+
+unsafe extern "C" {
+    fn zig_debug_allocator_free_allocations(allocations: *mut RawLiveAllocation, len: usize);
+}
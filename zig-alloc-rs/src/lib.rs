@@ -8,28 +8,114 @@
 /// FFI bindings
 mod ffi;
 
+/// Errors
+mod error;
+pub use error::CreateError;
+
 /// Debug allocator
 mod debug;
-pub use debug::ZigDebugAllocator;
+pub use debug::{DebugAllocatorConfig, SUPPORTED_STACK_TRACE_FRAMES, ZigDebugAllocator};
+
+/// Memory error events
+mod event;
+pub use event::{DebugEvent, DebugEventCallback, DebugEventKind};
+
+/// Leak reports
+mod leak;
+pub use leak::{
+    AllocationCheckpoint, LeakCheckError, LeakReport, LiveAllocation, SymbolizedLeakReport,
+};
+
+/// Stack trace symbolization
+mod symbolize;
+pub use symbolize::{SymbolizeOptions, SymbolizedFrame, symbolize};
 
 /// Debug global allocator
 mod debug_global;
 pub use debug_global::ZigGlobalDebugAllocator;
 
+/// Allocators other Zig allocators can be built on
+mod zig_allocator;
+pub use zig_allocator::ZigAllocator;
+
+/// External allocator
+///
+/// Rust allocators backing Zig allocators
+mod external;
+#[cfg(feature = "nightly")]
+pub use external::AllocatorAdapter;
+pub use external::ZigExternalAllocator;
+
 /// SMP allocator
 ///
 /// High performance, multi-thread
 mod smp;
 pub use smp::ZigSmpAllocator;
 
-/// Arena SMP
-mod arena_smp;
-pub use arena_smp::ZigArenaSmpAllocator;
+/// Page allocator
+///
+/// One mapping per allocation, straight from the OS
+mod page;
+pub use page::ZigPageAllocator;
+
+/// C allocator
+///
+/// libc `malloc`, as a baseline
+mod c;
+pub use c::ZigCAllocator;
+
+/// Arena allocator
+///
+/// Bulk deallocation, over SMP or any other Zig allocator
+mod arena;
+mod arena_drop;
+pub use arena::{
+    ArenaCheckpoint, ArenaStats, ArenaThreading, Local, ResetMode, Shared, ZigArenaAllocator,
+    ZigArenaSmpAllocator, ZigLocalArenaAllocator,
+};
+
+/// Failing allocator
+///
+/// Fault injection for out-of-memory testing
+mod failing;
+pub use failing::{FailingAllocatorConfig, FailingAllocatorStats, ZigFailingAllocator};
+
+/// Fixed buffer allocator
+///
+/// Heap-free allocation from a caller-provided buffer
+mod fixed_buffer;
+pub use fixed_buffer::ZigFixedBufferAllocator;
+
+/// Memory pool
+///
+/// Same-sized items with O(1) reuse
+mod pool;
+pub use pool::{MemoryPoolConfig, PoolBox, ZigMemoryPool};
+
+/// Stack fallback allocator
+///
+/// Inline buffer first, another allocator once full
+mod stack_fallback;
+pub use stack_fallback::{StackFallbackRef, ZigStackFallback};
+
+/// Allocator stacks
+///
+/// Zig allocators layered on top of each other at runtime
+mod stack;
+pub use stack::{StackBase, StackLayer, ZigAllocatorStack, ZigAllocatorStackBuilder};
 
 /// SMP global allocator
 mod smp_global;
 pub use smp_global::ZigGlobalSmpAllocator;
 
+/// Page global allocator
+mod page_global;
+pub use page_global::ZigGlobalPageAllocator;
+
+/// C global allocator
+mod c_global;
+pub use c_global::ZigGlobalCAllocator;
+
 /// Arena SMP global allocator
 mod arena_smp_global;
 pub use arena_smp_global::ZigGlobalArenaSmpAllocator;
@@ -0,0 +1,27 @@
+use crate::ZigPageAllocator;
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::LazyLock;
+
+/// A global allocator wrapper around ZigPageAllocator
+///
+/// This provides a global allocator implementation using Zig's page allocator,
+/// mapping every allocation from the OS. Mainly a baseline for comparisons.
+pub struct ZigGlobalPageAllocator;
+
+static ALLOCATOR: LazyLock<ZigPageAllocator> = LazyLock::new(ZigPageAllocator::new);
+
+unsafe impl GlobalAlloc for ZigGlobalPageAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { ALLOCATOR.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe {
+            ALLOCATOR.dealloc(ptr, layout);
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { ALLOCATOR.realloc(ptr, layout, new_size) }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::zig_allocator::ZigAllocator;
+use crate::{CreateError, ZigSmpAllocator};
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// Zig Memory Pool, handing out items of type `T`
+///
+/// Items are carved out of an arena backed by a [`ZigSmpAllocator`] unless
+/// built with [`Self::with_backing`], freed ones go on a free list for O(1)
+/// reuse. Memory only goes back to the backing allocator when the pool is
+/// dropped. Not `Sync`, the pool can't be shared across threads.
+pub struct ZigMemoryPool<T, B: ZigAllocator = ZigSmpAllocator> {
+    /// Inner allocator, dropped before `backing`
+    ffi_allocator: FfiAllocator,
+
+    /// Allocator the items are carved out of
+    backing: B,
+
+    /// Item type, and makes the pool `!Sync`
+    items: PhantomData<(T, Cell<()>)>,
+}
+
+/// Configuration for creating memory pools
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryPoolConfig {
+    /// Number of items allocated up front, and again after every reset
+    pub preheat: usize,
+
+    /// Whether the pool may grow past the preheated items
+    ///
+    /// A fixed pool never allocates after creation or reset, creating an item
+    /// fails once all the preheated ones are in use.
+    pub growable: bool,
+}
+
+impl Default for MemoryPoolConfig {
+    fn default() -> Self {
+        Self {
+            preheat: 0,
+            growable: true,
+        }
+    }
+}
+
+/// Configuration passed to the Zig side (`MemoryPool.Config`)
+#[repr(C)]
+struct RawMemoryPoolConfig {
+    /// `FfiAllocator` handle the items are carved out of
+    backing_allocator: *mut std::ffi::c_void,
+    /// Size of an item in bytes
+    item_size: usize,
+    /// Alignment of an item in bytes
+    item_alignment: usize,
+    /// Number of items allocated up front
+    preheat: usize,
+    /// Whether the pool may grow past the preheated items
+    growable: bool,
+}
+
+impl<T> ZigMemoryPool<T> {
+    /// Create a new growable memory pool
+    ///
+    /// Panics if the pool cannot be created, see [`Self::try_with_config`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_config(MemoryPoolConfig::default())
+    }
+
+    /// Create a new memory pool with the given configuration
+    ///
+    /// Panics if the pool cannot be created, see [`Self::try_with_config`]
+    #[must_use]
+    pub fn with_config(config: MemoryPoolConfig) -> Self {
+        Self::try_with_config(config).expect("failed to create Zig memory pool")
+    }
+
+    /// Create a new memory pool with the given configuration, reporting why creation failed
+    pub fn try_with_config(config: MemoryPoolConfig) -> Result<Self, CreateError> {
+        Self::try_with_backing(ZigSmpAllocator::try_new()?, config)
+    }
+}
+
+impl<T, B: ZigAllocator> ZigMemoryPool<T, B> {
+    /// Create a new memory pool carving its items out of `backing`
+    ///
+    /// Panics if the pool cannot be created, see [`Self::try_with_backing`]
+    #[must_use]
+    pub fn with_backing(backing: B, config: MemoryPoolConfig) -> Self {
+        Self::try_with_backing(backing, config).expect("failed to create Zig memory pool")
+    }
+
+    /// Create a new memory pool carving its items out of `backing`, reporting why creation failed
+    pub fn try_with_backing(backing: B, config: MemoryPoolConfig) -> Result<Self, CreateError> {
+        let layout = Layout::new::<T>();
+        let raw_config = RawMemoryPoolConfig {
+            backing_allocator: backing.ffi_handle(),
+            item_size: layout.size(),
+            item_alignment: layout.align(),
+            preheat: config.preheat,
+            growable: config.growable,
+        };
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe { zig_memory_pool_create(&raw_config, &mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            backing,
+            items: PhantomData,
+        })
+    }
+
+    /// Allocator the items are carved out of
+    #[must_use]
+    pub fn backing(&self) -> &B {
+        &self.backing
+    }
+
+    /// Move `value` into a pool item
+    ///
+    /// Panics through [`std::alloc::handle_alloc_error`] if the pool is exhausted,
+    /// see [`Self::try_create`].
+    pub fn create(&self, value: T) -> PoolBox<'_, T, B> {
+        match self.try_create(value) {
+            Ok(item) => item,
+            Err(_) => std::alloc::handle_alloc_error(Layout::new::<T>()),
+        }
+    }
+
+    /// Move `value` into a pool item, handing it back if the pool is exhausted
+    pub fn try_create(&self, value: T) -> Result<PoolBox<'_, T, B>, T> {
+        let layout = Layout::new::<T>();
+
+        // Zero-sized items take no room, they never reach Zig
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            match NonNull::new(self.ffi_allocator.alloc(layout)) {
+                Some(ptr) => ptr.cast(),
+                None => return Err(value),
+            }
+        };
+
+        unsafe { ptr.write(value) };
+        Ok(PoolBox { pool: self, ptr })
+    }
+
+    /// Drop the value of `item` and put its memory back on the free list
+    ///
+    /// Same as dropping `item`.
+    pub fn destroy(&self, item: PoolBox<'_, T, B>) {
+        debug_assert!(std::ptr::eq(item.pool, self), "item of another pool");
+        drop(item);
+    }
+
+    /// Free every item at once, keeping the memory for the next ones
+    ///
+    /// Taking `&mut self` guarantees no [`PoolBox`] is still alive, items leaked
+    /// with [`std::mem::forget`] are reclaimed without being dropped. Returns
+    /// `false` if the preheated items could not be allocated again.
+    pub fn reset(&mut self) -> bool {
+        unsafe { zig_memory_pool_reset(self.ffi_allocator.as_ptr()) }
+    }
+
+    /// Give the memory of an item back to the pool
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`Self::try_create`] and its value must have been dropped.
+    unsafe fn release(&self, ptr: NonNull<T>) {
+        let layout = Layout::new::<T>();
+        if layout.size() != 0 {
+            self.ffi_allocator.dealloc(ptr.as_ptr().cast(), layout);
+        }
+    }
+}
+
+impl<T> Default for ZigMemoryPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Value living in a [`ZigMemoryPool`] item, given back to the pool when dropped
+pub struct PoolBox<'a, T, B: ZigAllocator = ZigSmpAllocator> {
+    /// Pool the item comes from
+    pool: &'a ZigMemoryPool<T, B>,
+
+    /// Item holding the value
+    ptr: NonNull<T>,
+}
+
+impl<T, B: ZigAllocator> PoolBox<'_, T, B> {
+    /// Move the value out, giving the item back to the pool
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        let this = std::mem::ManuallyDrop::new(self);
+
+        unsafe {
+            let value = this.ptr.read();
+            this.pool.release(this.ptr);
+            value
+        }
+    }
+}
+
+impl<T, B: ZigAllocator> std::ops::Deref for PoolBox<'_, T, B> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T, B: ZigAllocator> std::ops::DerefMut for PoolBox<'_, T, B> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T: std::fmt::Debug, B: ZigAllocator> std::fmt::Debug for PoolBox<'_, T, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T, B: ZigAllocator> Drop for PoolBox<'_, T, B> {
+    fn drop(&mut self) {
+        unsafe {
+            self.ptr.drop_in_place();
+            self.pool.release(self.ptr);
+        }
+    }
+}
+
+unsafe extern "C" {
+    fn zig_memory_pool_create(
+        config: *const RawMemoryPoolConfig,
+        out_allocator: *mut *mut std::ffi::c_void,
+    ) -> CreateStatus;
+
+    fn zig_memory_pool_reset(allocator: *mut std::ffi::c_void) -> bool;
+}
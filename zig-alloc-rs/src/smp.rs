@@ -1,21 +1,89 @@
-use crate::ffi::FfiAllocator;
+use crate::CreateError;
+use crate::ffi::{CreateStatus, FfiAllocator};
+use crate::zig_allocator::{ZigAllocator, sealed};
 use std::alloc::GlobalAlloc;
 
 /// Zig SMP Allocator
+///
+/// Values built with [`Self::new`] share Zig's process-wide SMP heap. Those
+/// built with [`Self::isolated`] own an independent heap instead, given back
+/// to the OS when dropped or early with [`Self::trim`].
 pub struct ZigSmpAllocator {
     /// Inner allocator
     ffi_allocator: FfiAllocator,
+
+    /// Whether the allocator owns its heap
+    isolated: bool,
 }
 
 impl ZigSmpAllocator {
-    /// Create a new debug allocator
+    /// Create a new SMP allocator
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_new`]
     #[must_use]
     pub fn new() -> Self {
-        let allocator_ptr = unsafe { zig_smp_allocator_create() };
+        Self::try_new().expect("failed to create Zig SMP allocator")
+    }
+
+    /// Create a new SMP allocator, reporting why creation failed
+    pub fn try_new() -> Result<Self, CreateError> {
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe { zig_smp_allocator_create(&mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            isolated: false,
+        })
+    }
+
+    /// Create a new SMP allocator with its own heap
+    ///
+    /// Panics if the allocator cannot be created, see [`Self::try_isolated`]
+    #[must_use]
+    pub fn isolated() -> Self {
+        Self::try_isolated().expect("failed to create Zig SMP heap")
+    }
+
+    /// Create a new SMP allocator with its own heap, reporting why creation failed
+    ///
+    /// The heap keeps per-thread slabs like the shared one, but never hands
+    /// out memory of another heap. Dropping the allocator unmaps all its
+    /// memory at once, allocations still in use included.
+    pub fn try_isolated() -> Result<Self, CreateError> {
+        let mut allocator_ptr = std::ptr::null_mut();
+        let status = unsafe { zig_smp_heap_create(&mut allocator_ptr) };
+
+        Ok(Self {
+            ffi_allocator: FfiAllocator::from_create(status, allocator_ptr)?,
+            isolated: true,
+        })
+    }
 
-        Self {
-            ffi_allocator: FfiAllocator::new(allocator_ptr),
-        }
+    /// Whether the allocator owns its heap, see [`Self::isolated`]
+    #[must_use]
+    pub fn is_isolated(&self) -> bool {
+        self.isolated
+    }
+
+    /// Number of bytes the heap mapped from the OS, `None` for the shared heap
+    #[must_use]
+    pub fn mapped_bytes(&self) -> Option<usize> {
+        self.isolated
+            .then(|| unsafe { zig_smp_heap_mapped_bytes(self.ffi_allocator.as_ptr()) })
+    }
+
+    /// Give the free memory of the heap back to the OS, returning the number
+    /// of bytes released, `None` for the shared heap
+    ///
+    /// Slabs with every slot free are unmapped, and the pages of other free
+    /// slots larger than a page are discarded with `madvise(MADV_DONTNEED)`.
+    /// Those pages only count once, until their slot is handed out again.
+    /// Allocations from the heap wait until trimming is done.
+    ///
+    /// Zig's shared heap never gives memory back, only isolated heaps can be trimmed.
+    pub fn trim(&self) -> Option<usize> {
+        self.isolated
+            .then(|| unsafe { zig_smp_heap_trim(self.ffi_allocator.as_ptr()) })
     }
 }
 
@@ -25,6 +93,15 @@ impl Default for ZigSmpAllocator {
     }
 }
 
+impl sealed::Sealed for ZigSmpAllocator {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.ffi_allocator.as_ptr()
+    }
+}
+
+impl ZigAllocator for ZigSmpAllocator {}
+
 unsafe impl GlobalAlloc for ZigSmpAllocator {
     #[inline]
     unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
@@ -35,6 +112,13 @@ unsafe impl GlobalAlloc for ZigSmpAllocator {
     unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
         self.ffi_allocator.dealloc(ptr, layout);
     }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let new_layout =
+            unsafe { std::alloc::Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.ffi_allocator.realloc(ptr, layout, new_layout)
+    }
 }
 
 #[cfg(feature = "nightly")]
@@ -51,8 +135,44 @@ unsafe impl std::alloc::Allocator for ZigSmpAllocator {
     unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
         self.ffi_allocator.deallocate(ptr, layout);
     }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.ffi_allocator.shrink(ptr, old_layout, new_layout)
+    }
 }
 
 unsafe extern "C" {
-    fn zig_smp_allocator_create() -> *mut std::ffi::c_void;
+    fn zig_smp_allocator_create(out_allocator: *mut *mut std::ffi::c_void) -> CreateStatus;
+
+    fn zig_smp_heap_create(out_allocator: *mut *mut std::ffi::c_void) -> CreateStatus;
+
+    fn zig_smp_heap_trim(allocator: *mut std::ffi::c_void) -> usize;
+
+    fn zig_smp_heap_mapped_bytes(allocator: *mut std::ffi::c_void) -> usize;
 }
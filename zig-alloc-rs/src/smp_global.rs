@@ -4,10 +4,23 @@ use std::sync::LazyLock;
 
 /// A global allocator wrapper around ZigSmpAllocator
 ///
-/// This provides a global allocator implementation using Zig's SMP allocator.
+/// This provides a global allocator implementation using Zig's SMP allocator,
+/// over an isolated heap so its free memory can be given back to the OS.
 pub struct ZigGlobalSmpAllocator;
 
-static ALLOCATOR: LazyLock<ZigSmpAllocator> = LazyLock::new(ZigSmpAllocator::new);
+static ALLOCATOR: LazyLock<ZigSmpAllocator> = LazyLock::new(ZigSmpAllocator::isolated);
+
+impl ZigGlobalSmpAllocator {
+    /// Give the free memory of the global heap back to the OS, returning the
+    /// number of bytes released
+    ///
+    /// Useful after a load spike, see [`ZigSmpAllocator::trim`].
+    pub fn trim() -> usize {
+        ALLOCATOR
+            .trim()
+            .expect("the global SMP allocator uses an isolated heap")
+    }
+}
 
 unsafe impl GlobalAlloc for ZigGlobalSmpAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
@@ -19,4 +32,8 @@ unsafe impl GlobalAlloc for ZigGlobalSmpAllocator {
             ALLOCATOR.dealloc(ptr, layout);
         }
     }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { ALLOCATOR.realloc(ptr, layout, new_size) }
+    }
 }
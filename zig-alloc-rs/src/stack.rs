@@ -0,0 +1,352 @@
+use crate::zig_allocator::sealed::Sealed as _;
+use crate::zig_allocator::{ZigAllocator, sealed};
+use crate::{
+    ArenaStats, CreateError, DebugAllocatorConfig, DebugEvent, FailingAllocatorConfig,
+    FailingAllocatorStats, ZigArenaAllocator, ZigDebugAllocator, ZigFailingAllocator,
+    ZigPageAllocator, ZigSmpAllocator,
+};
+use std::alloc::GlobalAlloc;
+
+/// Zig allocators layered on top of each other at runtime
+///
+/// Built with [`ZigAllocatorStack::builder`], every layer requests its memory
+/// from the one below it, and the stack allocates from the top layer. Layers
+/// are dropped from the top down.
+///
+/// ```no_run
+/// use zigalloc::{DebugAllocatorConfig, StackBase, ZigAllocatorStack};
+///
+/// // Debug(Arena(Smp))
+/// let stack = ZigAllocatorStack::builder(StackBase::Smp)
+///     .arena()
+///     .debug(DebugAllocatorConfig::default())
+///     .build();
+/// ```
+pub struct ZigAllocatorStack {
+    /// Layers from the bottom up
+    layers: Vec<Layer>,
+}
+
+/// Bottom of a [`ZigAllocatorStack`], which every other layer builds on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StackBase {
+    /// Zig's SMP allocator
+    #[default]
+    Smp,
+
+    /// Zig's page allocator, mapping every allocation from the OS
+    Page,
+}
+
+/// Layer of a [`ZigAllocatorStack`], wrapping the layers below it
+#[derive(Clone, Copy, Debug)]
+pub enum StackLayer {
+    /// Arena, freeing everything when the stack is dropped
+    Arena,
+
+    /// Debug allocator with leak detection
+    ///
+    /// Configurations with `thread_safe` disabled are rejected with
+    /// [`CreateError::InvalidConfig`], the stack may be shared across threads.
+    Debug(DebugAllocatorConfig),
+
+    /// Failing allocator making chosen allocations fail
+    Failing(FailingAllocatorConfig),
+}
+
+/// Builder of a [`ZigAllocatorStack`], adding layers from the bottom up
+#[derive(Clone, Debug)]
+pub struct ZigAllocatorStackBuilder {
+    /// Bottom of the stack
+    base: StackBase,
+
+    /// Layers on top of `base`, from the bottom up
+    layers: Vec<StackLayer>,
+}
+
+/// Layer of a built stack
+enum Layer {
+    Smp(ZigSmpAllocator),
+    Page(ZigPageAllocator),
+    Arena(ZigArenaAllocator<LayerHandle>),
+    Debug(ZigDebugAllocator<LayerHandle>),
+    Failing(ZigFailingAllocator<LayerHandle>),
+}
+
+/// Handle of the layer below, which the stack keeps alive for as long as the layers above it
+struct LayerHandle(*mut std::ffi::c_void);
+
+unsafe impl Send for LayerHandle {}
+unsafe impl Sync for LayerHandle {}
+
+impl ZigAllocatorStack {
+    /// Start building a stack over `base`
+    #[must_use]
+    pub fn builder(base: StackBase) -> ZigAllocatorStackBuilder {
+        ZigAllocatorStackBuilder {
+            base,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Topmost debug layer, to inspect or report its leaks
+    #[must_use]
+    pub fn debug(&self) -> Option<&ZigDebugAllocator<impl ZigAllocator>> {
+        self.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Debug(debug) => Some(debug),
+            _ => None,
+        })
+    }
+
+    /// Set the event handler of the topmost debug layer, see
+    /// [`ZigDebugAllocator::set_event_handler`]
+    ///
+    /// Returns false if the stack has no debug layer.
+    pub fn set_debug_event_handler(
+        &mut self,
+        handler: impl Fn(&DebugEvent) + Send + Sync + 'static,
+    ) -> bool {
+        let debug = self.layers.iter_mut().rev().find_map(|layer| match layer {
+            Layer::Debug(debug) => Some(debug),
+            _ => None,
+        });
+
+        let Some(debug) = debug else {
+            return false;
+        };
+        debug.set_event_handler(handler);
+        true
+    }
+
+    /// Memory held by the topmost arena layer
+    #[must_use]
+    pub fn arena_stats(&self) -> Option<ArenaStats> {
+        self.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Arena(arena) => Some(arena.stats()),
+            _ => None,
+        })
+    }
+
+    /// Counters of the topmost failing layer
+    #[must_use]
+    pub fn failing_stats(&self) -> Option<FailingAllocatorStats> {
+        self.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Failing(failing) => Some(failing.stats()),
+            _ => None,
+        })
+    }
+
+    /// Number of layers, the base included
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Layer the stack allocates from
+    #[inline]
+    fn top(&self) -> &Layer {
+        self.layers.last().expect("allocator stack without a base")
+    }
+}
+
+impl ZigAllocatorStackBuilder {
+    /// Add `layer` on top of the layers added so far
+    #[must_use]
+    pub fn layer(mut self, layer: StackLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Add an arena on top of the layers added so far
+    #[must_use]
+    pub fn arena(self) -> Self {
+        self.layer(StackLayer::Arena)
+    }
+
+    /// Add a debug allocator on top of the layers added so far
+    #[must_use]
+    pub fn debug(self, config: DebugAllocatorConfig) -> Self {
+        self.layer(StackLayer::Debug(config))
+    }
+
+    /// Add a failing allocator on top of the layers added so far
+    #[must_use]
+    pub fn failing(self, config: FailingAllocatorConfig) -> Self {
+        self.layer(StackLayer::Failing(config))
+    }
+
+    /// Create every layer of the stack
+    ///
+    /// Panics if a layer cannot be created, see [`Self::try_build`]
+    #[must_use]
+    pub fn build(self) -> ZigAllocatorStack {
+        self.try_build()
+            .expect("failed to create Zig allocator stack")
+    }
+
+    /// Create every layer of the stack, reporting why creation failed
+    ///
+    /// The layers created before the failing one are dropped.
+    pub fn try_build(self) -> Result<ZigAllocatorStack, CreateError> {
+        // Layers are pushed on the stack as they come, so they are dropped in
+        // the right order if one fails
+        let mut stack = ZigAllocatorStack {
+            layers: Vec::with_capacity(self.layers.len() + 1),
+        };
+
+        stack.layers.push(match self.base {
+            StackBase::Smp => Layer::Smp(ZigSmpAllocator::try_new()?),
+            StackBase::Page => Layer::Page(ZigPageAllocator::try_new()?),
+        });
+
+        for layer in self.layers {
+            let backing = LayerHandle(stack.ffi_handle());
+
+            stack.layers.push(match layer {
+                StackLayer::Arena => Layer::Arena(ZigArenaAllocator::try_with_backing(backing)?),
+                StackLayer::Debug(config) => {
+                    Layer::Debug(ZigDebugAllocator::try_with_backing(backing, config)?)
+                }
+                StackLayer::Failing(config) => {
+                    Layer::Failing(ZigFailingAllocator::try_with_backing(backing, config)?)
+                }
+            });
+        }
+
+        Ok(stack)
+    }
+}
+
+impl Drop for ZigAllocatorStack {
+    fn drop(&mut self) {
+        // Each layer goes before the ones it requests its memory from
+        while let Some(layer) = self.layers.pop() {
+            drop(layer);
+        }
+    }
+}
+
+impl Layer {
+    /// Allocator interface of the layer
+    #[inline]
+    fn global(&self) -> &dyn GlobalAlloc {
+        match self {
+            Self::Smp(smp) => smp,
+            Self::Page(page) => page,
+            Self::Arena(arena) => arena,
+            Self::Debug(debug) => debug,
+            Self::Failing(failing) => failing,
+        }
+    }
+
+    /// Allocator interface of the layer
+    #[cfg(feature = "nightly")]
+    #[inline]
+    fn allocator(&self) -> &dyn std::alloc::Allocator {
+        match self {
+            Self::Smp(smp) => smp,
+            Self::Page(page) => page,
+            Self::Arena(arena) => arena,
+            Self::Debug(debug) => debug,
+            Self::Failing(failing) => failing,
+        }
+    }
+}
+
+impl sealed::Sealed for Layer {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        match self {
+            Self::Smp(smp) => smp.ffi_handle(),
+            Self::Page(page) => page.ffi_handle(),
+            Self::Arena(arena) => arena.ffi_handle(),
+            Self::Debug(debug) => debug.ffi_handle(),
+            Self::Failing(failing) => failing.ffi_handle(),
+        }
+    }
+}
+
+impl sealed::Sealed for LayerHandle {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.0
+    }
+}
+
+impl ZigAllocator for LayerHandle {}
+
+impl sealed::Sealed for ZigAllocatorStack {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        self.top().ffi_handle()
+    }
+}
+
+impl ZigAllocator for ZigAllocatorStack {}
+
+unsafe impl GlobalAlloc for ZigAllocatorStack {
+    #[inline]
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        unsafe { self.top().global().alloc(layout) }
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        unsafe { self.top().global().dealloc(ptr, layout) };
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        unsafe { self.top().global().realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl std::alloc::Allocator for ZigAllocatorStack {
+    #[inline]
+    fn allocate(
+        &self,
+        layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        self.top().allocator().allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        unsafe { self.top().allocator().deallocate(ptr, layout) };
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        unsafe { self.top().allocator().grow(ptr, old_layout, new_layout) }
+    }
+
+    #[inline]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        unsafe {
+            self.top()
+                .allocator()
+                .grow_zeroed(ptr, old_layout, new_layout)
+        }
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: std::ptr::NonNull<u8>,
+        old_layout: std::alloc::Layout,
+        new_layout: std::alloc::Layout,
+    ) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+        unsafe { self.top().allocator().shrink(ptr, old_layout, new_layout) }
+    }
+}
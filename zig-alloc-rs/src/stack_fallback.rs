@@ -0,0 +1,284 @@
+use crate::ZigSmpAllocator;
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::{Cell, UnsafeCell};
+use std::mem::MaybeUninit;
+#[cfg(feature = "nightly")]
+use std::ptr::NonNull;
+
+/// Inline buffer of `N` bytes, falling back to another allocator once full
+///
+/// Same strategy as Zig's `std.heap.stackFallback`: allocations are served from
+/// the buffer first, only the most recent one can be freed or resized in place.
+/// Implemented in Rust only, since the buffer size is a compile-time parameter
+/// on both sides.
+///
+/// Allocations go through [`Self::allocator`], like `.get()` in Zig: the
+/// returned handle borrows the buffer, which therefore can't move while in use.
+///
+/// Not `Sync`, the allocator can't be shared across threads.
+pub struct ZigStackFallback<const N: usize, A: GlobalAlloc = ZigSmpAllocator> {
+    /// Inline buffer allocations are served from first
+    buffer: UnsafeCell<[MaybeUninit<u8>; N]>,
+
+    /// Offset of the next allocation in `buffer`
+    end_index: Cell<usize>,
+
+    /// Allocator serving what doesn't fit in `buffer`
+    fallback: A,
+}
+
+impl<const N: usize, A: GlobalAlloc> ZigStackFallback<N, A> {
+    /// Create a new stack fallback allocator over `fallback`
+    #[must_use]
+    pub const fn new(fallback: A) -> Self {
+        Self {
+            buffer: UnsafeCell::new([MaybeUninit::uninit(); N]),
+            end_index: Cell::new(0),
+            fallback,
+        }
+    }
+
+    /// Allocator handing out memory from the buffer first
+    ///
+    /// The buffer can't move while the handle is in use:
+    ///
+    /// ```compile_fail
+    /// # use std::alloc::{GlobalAlloc, Layout};
+    /// let stack = zigalloc::ZigStackFallback::<64, zigalloc::ZigSmpAllocator>::default();
+    /// let allocator = stack.allocator();
+    /// let moved = stack;
+    /// unsafe { allocator.alloc(Layout::new::<u64>()) };
+    /// ```
+    #[must_use]
+    pub fn allocator(&self) -> StackFallbackRef<'_, N, A> {
+        StackFallbackRef { owner: self }
+    }
+
+    /// Allocator serving what doesn't fit in the buffer
+    #[must_use]
+    pub fn fallback(&self) -> &A {
+        &self.fallback
+    }
+
+    /// Number of bytes of the buffer used so far, alignment padding included
+    #[must_use]
+    pub fn end_index(&self) -> usize {
+        self.end_index.get()
+    }
+
+    /// Start of the inline buffer, at its current address
+    #[inline]
+    fn base(&self) -> *mut u8 {
+        self.buffer.get().cast()
+    }
+
+    /// Whether `ptr` was handed out from the inline buffer
+    #[inline]
+    fn owns(&self, ptr: *mut u8) -> bool {
+        (self.base().addr()..self.base().addr() + N).contains(&ptr.addr())
+    }
+
+    /// Allocate from the buffer, or return null if it doesn't fit
+    #[inline]
+    fn alloc_in_buffer(&self, layout: Layout) -> *mut u8 {
+        let end_index = self.end_index.get();
+        let start = self.base().wrapping_add(end_index);
+
+        let Some(new_end_index) = end_index
+            .checked_add(start.align_offset(layout.align()))
+            .and_then(|aligned| aligned.checked_add(layout.size()))
+            .filter(|&new_end_index| new_end_index <= N)
+        else {
+            return std::ptr::null_mut();
+        };
+
+        self.end_index.set(new_end_index);
+        self.base().wrapping_add(new_end_index - layout.size())
+    }
+
+    /// Whether `ptr` is the most recent allocation of the buffer
+    #[inline]
+    fn is_last_allocation(&self, ptr: *mut u8, size: usize) -> bool {
+        ptr.addr() + size == self.base().addr() + self.end_index.get()
+    }
+
+    /// Resize `ptr` in place, which only works for the most recent allocation of the buffer
+    #[inline]
+    fn resize_in_buffer(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> bool {
+        if !self.is_last_allocation(ptr, old_size) {
+            return new_size <= old_size;
+        }
+
+        let new_end_index = ptr.addr() - self.base().addr() + new_size;
+        if new_end_index > N {
+            return false;
+        }
+
+        self.end_index.set(new_end_index);
+        true
+    }
+
+    /// Shared implementation of `realloc`, `grow` and `shrink`
+    ///
+    /// Returns null on failure, in which case `ptr` is left untouched.
+    fn reallocate(&self, ptr: *mut u8, old_layout: Layout, new_layout: Layout) -> *mut u8 {
+        if self.owns(ptr) {
+            let aligned = ptr.addr().is_multiple_of(new_layout.align());
+            if aligned && self.resize_in_buffer(ptr, old_layout.size(), new_layout.size()) {
+                return ptr;
+            }
+        } else if old_layout.align() == new_layout.align() {
+            return unsafe { self.fallback.realloc(ptr, old_layout, new_layout.size()) };
+        }
+
+        let new_ptr = self.allocate_raw(new_layout);
+        if !new_ptr.is_null() {
+            unsafe {
+                new_ptr.copy_from_nonoverlapping(ptr, old_layout.size().min(new_layout.size()));
+                self.deallocate_raw(ptr, old_layout);
+            }
+        }
+        new_ptr
+    }
+
+    /// Allocate from the buffer, or from the fallback if it doesn't fit
+    #[inline]
+    fn allocate_raw(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.alloc_in_buffer(layout);
+        if !ptr.is_null() {
+            return ptr;
+        }
+
+        unsafe { self.fallback.alloc(layout) }
+    }
+
+    /// Free `ptr`, given back to the buffer only if it is its most recent allocation
+    #[inline]
+    unsafe fn deallocate_raw(&self, ptr: *mut u8, layout: Layout) {
+        if !self.owns(ptr) {
+            unsafe { self.fallback.dealloc(ptr, layout) };
+            return;
+        }
+
+        if self.is_last_allocation(ptr, layout.size()) {
+            self.end_index.set(ptr.addr() - self.base().addr());
+        }
+    }
+}
+
+impl<const N: usize, A: GlobalAlloc + Default> Default for ZigStackFallback<N, A> {
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+/// Handle allocating from a [`ZigStackFallback`], see [`ZigStackFallback::allocator`]
+///
+/// Memory handed out from the buffer lives inside the [`ZigStackFallback`],
+/// which stays borrowed, and so in place, as long as the handle is alive.
+pub struct StackFallbackRef<'a, const N: usize, A: GlobalAlloc = ZigSmpAllocator> {
+    owner: &'a ZigStackFallback<N, A>,
+}
+
+impl<const N: usize, A: GlobalAlloc> Clone for StackFallbackRef<'_, N, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<const N: usize, A: GlobalAlloc> Copy for StackFallbackRef<'_, N, A> {}
+
+/// Raw pointers aren't tied to the handle: memory taken from the buffer must
+/// be freed before the [`ZigStackFallback`] moves.
+unsafe impl<const N: usize, A: GlobalAlloc> GlobalAlloc for StackFallbackRef<'_, N, A> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.owner.allocate_raw(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.owner.deallocate_raw(ptr, layout) };
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        self.owner.reallocate(ptr, layout, new_layout)
+    }
+}
+
+#[cfg(feature = "nightly")]
+unsafe impl<const N: usize, A: GlobalAlloc> std::alloc::Allocator for StackFallbackRef<'_, N, A> {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, std::alloc::AllocError> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+
+        let ptr = NonNull::new(unsafe { self.alloc(layout) }).ok_or(std::alloc::AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            unsafe { self.dealloc(ptr.as_ptr(), layout) };
+        }
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, std::alloc::AllocError> {
+        unsafe { self.resize(ptr, old_layout, new_layout) }
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, std::alloc::AllocError> {
+        unsafe { self.resize(ptr, old_layout, new_layout) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<const N: usize, A: GlobalAlloc> StackFallbackRef<'_, N, A> {
+    /// Shared implementation of `grow` and `shrink`, handling zero-sized layouts
+    unsafe fn resize(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, std::alloc::AllocError> {
+        use std::alloc::Allocator;
+
+        if old_layout.size() == 0 {
+            return self.allocate(new_layout);
+        }
+
+        if new_layout.size() == 0 {
+            unsafe { self.deallocate(ptr, old_layout) };
+            return Ok(dangling(new_layout));
+        }
+
+        let new_ptr = self.owner.reallocate(ptr.as_ptr(), old_layout, new_layout);
+        let new_ptr = NonNull::new(new_ptr).ok_or(std::alloc::AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(new_ptr, new_layout.size()))
+    }
+}
+
+/// Well-aligned, non-null pointer for a zero-sized allocation
+#[cfg(feature = "nightly")]
+#[inline]
+fn dangling(layout: Layout) -> NonNull<[u8]> {
+    let ptr = std::ptr::without_provenance_mut::<u8>(layout.align());
+    let non_null_ptr = unsafe { NonNull::new_unchecked(ptr) };
+    NonNull::slice_from_raw_parts(non_null_ptr, 0)
+}
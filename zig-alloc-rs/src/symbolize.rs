@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+/// Options for [`symbolize`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SymbolizeOptions {
+    /// Skip the leading allocator frames (`zigalloc`, `alloc::raw_vec`,
+    /// `__rust_alloc`, Zig internals...), so the first frame is user code
+    pub skip_allocator_frames: bool,
+}
+
+/// Stack frame resolved from a return address
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolizedFrame {
+    /// Return address the frame was resolved from
+    pub address: usize,
+
+    /// Demangled function name, without the hash suffix
+    pub function: Option<String>,
+
+    /// Source file
+    pub file: Option<PathBuf>,
+
+    /// Line in `file`
+    pub line: Option<u32>,
+}
+
+impl SymbolizedFrame {
+    /// Whether the frame belongs to the allocation machinery rather than user code
+    ///
+    /// Frames without a resolved name, as in stripped binaries, count as user code.
+    fn is_allocator_internal(&self) -> bool {
+        self.function.as_deref().is_some_and(|function| {
+            let function = function.trim_start_matches('<');
+            ALLOCATOR_FRAME_PREFIXES
+                .iter()
+                .any(|prefix| function.starts_with(prefix))
+        })
+    }
+
+    /// Whether the frame belongs to the standard library or the allocation machinery
+    ///
+    /// Frames without a resolved name count as user code, as above.
+    pub(crate) fn is_runtime(&self) -> bool {
+        self.is_allocator_internal()
+            || self
+                .function
+                .as_deref()
+                .is_some_and(|function| function.trim_start_matches('<').starts_with("std::"))
+    }
+}
+
+impl std::fmt::Display for SymbolizedFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#x}: ", self.address)?;
+
+        match &self.function {
+            Some(function) => f.write_str(function)?,
+            None => f.write_str("<unknown>")?,
+        }
+
+        if let Some(file) = &self.file {
+            write!(f, " at {}", file.display())?;
+            if let Some(line) = self.line {
+                write!(f, ":{line}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Function name prefixes of frames skipped by [`SymbolizeOptions::skip_allocator_frames`]
+const ALLOCATOR_FRAME_PREFIXES: &[&str] = &[
+    // This crate
+    "zigalloc::",
+    // Rust standard library
+    "alloc::",
+    "core::",
+    "std::alloc::",
+    "__rust_",
+    "__rdl_",
+    "__rg_",
+    // Zig side
+    "zig_",
+    "debug_allocator.",
+    "ffi.",
+    "heap.",
+    "mem.Allocator.",
+];
+
+/// Resolve return addresses, innermost first, to functions and source locations
+///
+/// An address may resolve to several frames when functions were inlined.
+/// Addresses without debug information are kept with empty fields.
+#[must_use]
+pub fn symbolize(return_addresses: &[usize], options: SymbolizeOptions) -> Vec<SymbolizedFrame> {
+    let mut frames = Vec::with_capacity(return_addresses.len());
+
+    for &address in return_addresses {
+        let first_frame = frames.len();
+
+        // A return address points after the call, step back into the call instruction
+        let call_address = address.saturating_sub(1);
+        backtrace::resolve(call_address as *mut std::ffi::c_void, |symbol| {
+            frames.push(SymbolizedFrame {
+                address,
+                function: symbol.name().map(|name| format!("{name:#}")),
+                file: symbol.filename().map(PathBuf::from),
+                line: symbol.lineno(),
+            });
+        });
+
+        if frames.len() == first_frame {
+            frames.push(SymbolizedFrame {
+                address,
+                function: None,
+                file: None,
+                line: None,
+            });
+        }
+    }
+
+    if options.skip_allocator_frames {
+        let user_frame = frames
+            .iter()
+            .position(|frame| !frame.is_allocator_internal())
+            .unwrap_or(frames.len());
+        frames.drain(..user_frame);
+    }
+
+    frames
+}
@@ -0,0 +1,23 @@
+/// Allocator of this crate that other Zig allocators can be built on
+///
+/// Implemented for the Zig allocator types of this crate and references to
+/// them, see [`crate::ZigArenaAllocator::with_backing`]. This trait is sealed.
+pub trait ZigAllocator: sealed::Sealed {}
+
+pub(crate) mod sealed {
+    /// Access to the Zig handle, kept out of the public API
+    pub trait Sealed {
+        /// `FfiAllocator` handle passed to the Zig exports
+        #[doc(hidden)]
+        fn ffi_handle(&self) -> *mut std::ffi::c_void;
+    }
+}
+
+impl<T: ZigAllocator + ?Sized> sealed::Sealed for &T {
+    #[inline]
+    fn ffi_handle(&self) -> *mut std::ffi::c_void {
+        (**self).ffi_handle()
+    }
+}
+
+impl<T: ZigAllocator + ?Sized> ZigAllocator for &T {}
@@ -0,0 +1,212 @@
+//! Allocator stacks composed at runtime.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::{Arc, Mutex};
+use zigalloc::{
+    CreateError, DebugAllocatorConfig, DebugEventKind, FailingAllocatorConfig, StackBase,
+    StackLayer, ZigAllocatorStack, ZigArenaAllocator,
+};
+
+const LAYOUT: Layout = match Layout::from_size_align(64, 8) {
+    Ok(layout) => layout,
+    Err(_) => unreachable!(),
+};
+
+#[test]
+fn debug_over_arena_over_smp() {
+    let stack = ZigAllocatorStack::builder(StackBase::Smp)
+        .arena()
+        .debug(DebugAllocatorConfig::default())
+        .build();
+    assert_eq!(stack.depth(), 3);
+
+    let debug = stack.debug().unwrap();
+    let checkpoint = debug.checkpoint();
+
+    let ptr = unsafe { stack.alloc(LAYOUT) };
+    assert!(!ptr.is_null());
+    unsafe { ptr.write_bytes(0xAB, LAYOUT.size()) };
+
+    // Allocations go through the debug layer, which takes its pages from the arena
+    assert_eq!(debug.total_requested_bytes(), LAYOUT.size());
+    assert!(debug.check_leaks_since(checkpoint).is_err());
+    assert!(stack.arena_stats().unwrap().capacity > 0);
+
+    unsafe { stack.dealloc(ptr, LAYOUT) };
+    debug.check_leaks_since(checkpoint).unwrap();
+    assert_eq!(stack.failing_stats(), None);
+}
+
+#[test]
+fn failing_over_debug_over_page() {
+    let stack = ZigAllocatorStack::builder(StackBase::Page)
+        .debug(DebugAllocatorConfig::default())
+        .failing(FailingAllocatorConfig {
+            fail_index: Some(1),
+            ..Default::default()
+        })
+        .build();
+
+    let first = unsafe { stack.alloc(LAYOUT) };
+    assert!(!first.is_null());
+    assert!(unsafe { stack.alloc(LAYOUT) }.is_null());
+
+    // The failed allocation never reached the debug layer
+    assert_eq!(
+        stack.debug().unwrap().total_requested_bytes(),
+        LAYOUT.size()
+    );
+
+    unsafe { stack.dealloc(first, LAYOUT) };
+
+    let stats = stack.failing_stats().unwrap();
+    assert_eq!(stats.allocations, 1);
+    assert_eq!(stats.deallocations, 1);
+    assert_eq!(stats.failures, 1);
+}
+
+#[test]
+fn layers_chosen_at_runtime() {
+    let layers = [
+        StackLayer::Failing(FailingAllocatorConfig::default()),
+        StackLayer::Arena,
+        StackLayer::Debug(DebugAllocatorConfig::default()),
+    ];
+    let stack = layers
+        .into_iter()
+        .fold(
+            ZigAllocatorStack::builder(StackBase::default()),
+            |builder, layer| builder.layer(layer),
+        )
+        .build();
+    assert_eq!(stack.depth(), 4);
+
+    let ptr = unsafe { stack.realloc(stack.alloc(LAYOUT), LAYOUT, 4096) };
+    assert!(!ptr.is_null());
+    unsafe { stack.dealloc(ptr, Layout::from_size_align(4096, LAYOUT.align()).unwrap()) };
+
+    // The failing layer serves the arena buffers
+    let stats = stack.failing_stats().unwrap();
+    assert!(stats.allocations > 0);
+    assert_eq!(stats.failures, 0);
+}
+
+#[test]
+fn base_alone() {
+    let stack = ZigAllocatorStack::builder(StackBase::Page).build();
+    assert_eq!(stack.depth(), 1);
+    assert!(stack.debug().is_none());
+    assert_eq!(stack.arena_stats(), None);
+
+    let ptr = unsafe { stack.alloc(LAYOUT) };
+    assert!(!ptr.is_null());
+    unsafe { stack.dealloc(ptr, LAYOUT) };
+}
+
+#[test]
+fn invalid_layer_is_reported() {
+    let result = ZigAllocatorStack::builder(StackBase::Smp)
+        .arena()
+        .failing(FailingAllocatorConfig {
+            failure_probability: 2.0,
+            ..Default::default()
+        })
+        .try_build();
+    assert!(matches!(result, Err(CreateError::InvalidConfig)));
+
+    let result = ZigAllocatorStack::builder(StackBase::Smp)
+        .debug(DebugAllocatorConfig {
+            thread_safe: false,
+            ..Default::default()
+        })
+        .try_build();
+    assert!(matches!(result, Err(CreateError::InvalidConfig)));
+}
+
+#[test]
+fn stack_backing_an_arena() {
+    let stack = ZigAllocatorStack::builder(StackBase::Smp)
+        .debug(DebugAllocatorConfig::default())
+        .build();
+
+    {
+        let arena = ZigArenaAllocator::with_backing(&stack);
+        arena.alloc_str("hello");
+        assert_eq!(
+            stack.debug().unwrap().total_requested_bytes(),
+            arena.stats().capacity
+        );
+    }
+
+    assert_eq!(stack.debug().unwrap().total_requested_bytes(), 0);
+}
+
+#[test]
+fn shared_across_threads() {
+    let stack = ZigAllocatorStack::builder(StackBase::Smp)
+        .arena()
+        .debug(DebugAllocatorConfig::default())
+        .build();
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    let ptr = unsafe { stack.alloc(LAYOUT) };
+                    assert!(!ptr.is_null());
+                    unsafe { stack.dealloc(ptr, LAYOUT) };
+                }
+            });
+        }
+    });
+
+    assert_eq!(stack.debug().unwrap().total_requested_bytes(), 0);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn collections_in_a_stack() {
+    let stack = ZigAllocatorStack::builder(StackBase::Page)
+        .arena()
+        .debug(DebugAllocatorConfig::default())
+        .build();
+
+    let mut values = Vec::new_in(&stack);
+    values.extend(0..1000);
+    assert_eq!(values.iter().sum::<i32>(), 499_500);
+    drop(values);
+
+    assert_eq!(stack.debug().unwrap().total_requested_bytes(), 0);
+}
+
+#[test]
+fn debug_layer_reports_events() {
+    let mut stack = ZigAllocatorStack::builder(StackBase::Page)
+        .debug(DebugAllocatorConfig {
+            never_unmap: true,
+            retain_metadata: true,
+            ..DebugAllocatorConfig::default()
+        })
+        .build();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    assert!(
+        stack.set_debug_event_handler(move |event| recorded.lock().unwrap().push(event.kind()))
+    );
+
+    unsafe {
+        let ptr = stack.alloc(LAYOUT);
+        stack.dealloc(ptr, LAYOUT);
+        stack.dealloc(ptr, LAYOUT);
+    }
+
+    assert_eq!(*events.lock().unwrap(), [DebugEventKind::DoubleFree]);
+}
+
+#[test]
+fn event_handler_needs_a_debug_layer() {
+    let mut stack = ZigAllocatorStack::builder(StackBase::Smp).build();
+    assert!(!stack.set_debug_event_handler(|_| {}));
+}
@@ -0,0 +1,47 @@
+//! Arenas over other Zig allocators.
+
+use zigalloc::{ResetMode, ZigArenaAllocator, ZigArenaSmpAllocator, ZigDebugAllocator};
+
+#[test]
+fn arena_over_debug_allocator() {
+    let debug = ZigDebugAllocator::new();
+    let checkpoint = debug.checkpoint();
+
+    {
+        let arena = ZigArenaAllocator::with_backing(&debug);
+        let values = arena.alloc_slice_fill_with(1000, |i| i);
+        assert_eq!(values[999], 999);
+
+        // The arena buffers are tracked by the debug allocator
+        let capacity = arena.stats().capacity;
+        assert_eq!(debug.total_requested_bytes(), capacity);
+        assert!(debug.check_leaks_since(checkpoint).is_err());
+    }
+
+    assert_eq!(debug.total_requested_bytes(), 0);
+    debug.check_leaks_since(checkpoint).unwrap();
+}
+
+#[test]
+fn arena_owning_debug_allocator() {
+    let mut arena = ZigArenaAllocator::with_backing(ZigDebugAllocator::new());
+    arena.alloc_str("hello");
+
+    arena.reset(ResetMode::FreeAll);
+    assert_eq!(arena.backing().total_requested_bytes(), 0);
+}
+
+#[test]
+fn arena_over_arena() {
+    let outer = ZigArenaSmpAllocator::new();
+    let inner = ZigArenaAllocator::with_backing(&outer);
+
+    assert_eq!(inner.alloc_str("nested"), "nested");
+    assert!(outer.stats().used >= inner.stats().capacity);
+}
+
+#[test]
+fn default_arena_is_smp_backed() {
+    let arena = ZigArenaSmpAllocator::new();
+    assert_eq!(*arena.alloc_value(42u64), 42);
+}
@@ -0,0 +1,109 @@
+//! Rolling the arena back to a checkpoint.
+
+use std::sync::{Arc, Mutex};
+use zigalloc::{ResetMode, ZigArenaSmpAllocator};
+
+#[test]
+fn rollback_reuses_memory() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    arena.alloc_str("kept");
+
+    let checkpoint = arena.checkpoint();
+    let speculative = std::ptr::from_mut(arena.alloc_slice_fill_with(64, |i| i)).cast::<usize>();
+    arena.rollback(checkpoint);
+
+    let reused = std::ptr::from_mut(arena.alloc_slice_fill_with(64, |i| i * 2)).cast::<usize>();
+    assert_eq!(speculative, reused);
+}
+
+#[test]
+fn backtracking_parser() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    let mut parsed = 0;
+
+    for attempt in 0..100 {
+        let checkpoint = arena.checkpoint();
+
+        // Large enough to spill over several arena buffers
+        let tokens = arena.alloc_slice_fill_with(4096, |i| i + attempt);
+        if tokens[0].is_multiple_of(2) {
+            parsed += tokens.len();
+        }
+
+        arena.rollback(checkpoint);
+    }
+
+    assert_eq!(parsed, 50 * 4096);
+}
+
+#[test]
+fn rollback_runs_newer_destructors() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut arena = ZigArenaSmpAllocator::new();
+
+    struct Logged(&'static str, Arc<Mutex<Vec<&'static str>>>);
+
+    impl Drop for Logged {
+        fn drop(&mut self) {
+            self.1.lock().unwrap().push(self.0);
+        }
+    }
+
+    arena.alloc_with_drop(Logged("before", Arc::clone(&log)));
+    let checkpoint = arena.checkpoint();
+    arena.alloc_with_drop(Logged("after", Arc::clone(&log)));
+
+    arena.rollback(checkpoint);
+    assert_eq!(*log.lock().unwrap(), ["after"]);
+
+    drop(arena);
+    assert_eq!(*log.lock().unwrap(), ["after", "before"]);
+}
+
+#[test]
+#[should_panic(expected = "stale or foreign arena checkpoint")]
+fn rollback_after_reset_panics() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    arena.alloc_value(1u64);
+
+    let checkpoint = arena.checkpoint();
+    arena.reset(ResetMode::RetainCapacity);
+    arena.rollback(checkpoint);
+}
+
+#[test]
+#[should_panic(expected = "stale or foreign arena checkpoint")]
+fn rollback_to_other_arena_panics() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    let other = ZigArenaSmpAllocator::new();
+    arena.alloc_value(1u64);
+    other.alloc_value(1u64);
+
+    arena.rollback(other.checkpoint());
+}
+
+#[test]
+#[should_panic(expected = "stale or foreign arena checkpoint")]
+fn rollback_to_checkpoint_rolled_past_panics() {
+    let mut arena = ZigArenaSmpAllocator::new();
+
+    let outer = arena.checkpoint();
+    arena.alloc_value(1u64);
+    let inner = arena.checkpoint();
+    arena.rollback(outer);
+
+    // Allocating again moves past the position of `inner`, which stays stale
+    arena.alloc_slice_fill_with(64, |i| i);
+    arena.rollback(inner);
+}
+
+#[test]
+#[should_panic(expected = "stale or foreign arena checkpoint")]
+fn rollback_to_empty_other_arena_panics() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    let other = ZigArenaSmpAllocator::new();
+    arena.alloc_value(1u64);
+
+    // Taken before the other arena allocated anything
+    arena.rollback(other.checkpoint());
+}
@@ -0,0 +1,78 @@
+//! Destructors of values placed in the arena with the `*_with_drop` methods.
+
+use std::sync::{Arc, Mutex};
+use zigalloc::{ResetMode, ZigArenaSmpAllocator};
+
+/// Records its id in a shared log when dropped
+struct Tracked {
+    id: usize,
+    log: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.log.lock().unwrap().push(self.id);
+    }
+}
+
+#[test]
+fn drops_run_in_reverse_order_on_drop() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let arena = ZigArenaSmpAllocator::new();
+
+    for id in 0..3 {
+        arena.alloc_with_drop(Tracked {
+            id,
+            log: Arc::clone(&log),
+        });
+    }
+    assert!(log.lock().unwrap().is_empty());
+
+    drop(arena);
+    assert_eq!(*log.lock().unwrap(), [2, 1, 0]);
+}
+
+#[test]
+fn drops_run_on_reset() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut arena = ZigArenaSmpAllocator::new();
+
+    arena.alloc_slice_fill_with_drop(3, |id| Tracked {
+        id,
+        log: Arc::clone(&log),
+    });
+    arena.reset(ResetMode::RetainCapacity);
+    assert_eq!(*log.lock().unwrap(), [0, 1, 2]);
+
+    // Already run, not run again
+    drop(arena);
+    assert_eq!(log.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn holds_owning_values() {
+    let shared = Arc::new(42);
+    let arena = ZigArenaSmpAllocator::new();
+
+    let text = arena.alloc_with_drop(String::from("hello"));
+    text.push_str(", world");
+    let handle = arena.alloc_with_drop(Arc::clone(&shared));
+
+    assert_eq!(text, "hello, world");
+    assert_eq!(**handle, 42);
+    assert_eq!(Arc::strong_count(&shared), 2);
+
+    drop(arena);
+    assert_eq!(Arc::strong_count(&shared), 1);
+}
+
+#[test]
+fn plain_alloc_does_not_drop() {
+    let shared = Arc::new(());
+    let arena = ZigArenaSmpAllocator::new();
+
+    arena.alloc_value(Arc::clone(&shared));
+    drop(arena);
+
+    assert_eq!(Arc::strong_count(&shared), 2);
+}
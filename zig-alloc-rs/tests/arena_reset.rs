@@ -0,0 +1,72 @@
+//! Resetting the arena to reuse it across cycles.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{ResetMode, ZigArenaSmpAllocator};
+
+/// Allocate and fill a few buffers, as one cycle of work would
+fn run_cycle(arena: &ZigArenaSmpAllocator) -> *mut u8 {
+    let layout = Layout::from_size_align(1024, 8).unwrap();
+    let mut first: *mut u8 = std::ptr::null_mut();
+
+    for i in 0..4 {
+        let ptr = unsafe { arena.alloc(layout) };
+        assert!(!ptr.is_null());
+        unsafe { ptr.write_bytes(i, layout.size()) };
+
+        if first.is_null() {
+            first = ptr;
+        }
+    }
+
+    first
+}
+
+#[test]
+fn retain_capacity_reuses_the_same_memory() {
+    let mut arena = ZigArenaSmpAllocator::new();
+
+    let first_cycle = run_cycle(&arena);
+    assert!(arena.reset(ResetMode::RetainCapacity));
+    let second_cycle = run_cycle(&arena);
+
+    // The retained buffer is reused from its start
+    assert_eq!(first_cycle, second_cycle);
+}
+
+#[test]
+fn every_mode_resets() {
+    let mut arena = ZigArenaSmpAllocator::new();
+
+    for mode in [
+        ResetMode::FreeAll,
+        ResetMode::RetainCapacity,
+        ResetMode::RetainWithLimit(2048),
+        ResetMode::RetainWithLimit(0),
+    ] {
+        run_cycle(&arena);
+        assert!(arena.reset(mode));
+    }
+
+    run_cycle(&arena);
+}
+
+#[cfg(feature = "nightly")]
+mod allocator_api {
+    use zigalloc::{ResetMode, ZigArenaSmpAllocator};
+
+    #[test]
+    fn collections_are_reused_per_cycle() {
+        let mut arena = ZigArenaSmpAllocator::new();
+
+        for _ in 0..10 {
+            let mut data = Vec::<u32, &ZigArenaSmpAllocator>::new_in(&arena);
+            data.extend(0..1000);
+            assert_eq!(data.iter().sum::<u32>(), 499_500);
+
+            // `data` borrows the arena, it has to go before resetting
+            drop(data);
+            arena.reset(ResetMode::RetainCapacity);
+        }
+    }
+}
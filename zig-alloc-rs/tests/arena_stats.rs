@@ -0,0 +1,75 @@
+//! Arena statistics.
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{ArenaStats, ResetMode, ZigArenaSmpAllocator};
+
+#[test]
+fn empty_arena() {
+    let arena = ZigArenaSmpAllocator::new();
+    assert_eq!(arena.stats(), ArenaStats::default());
+}
+
+#[test]
+fn tracks_used_bytes_and_chunks() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    arena.alloc_slice_copy(&[0u8; 100]);
+
+    let stats = arena.stats();
+    assert_eq!(stats.used, 100);
+    assert_eq!(stats.chunks, 1);
+    assert!(stats.capacity >= 100);
+
+    // Doesn't fit in the first buffer, whose tail is wasted
+    arena.alloc_slice_fill_with(2 * stats.capacity, |_| 0u8);
+    let grown = arena.stats();
+    assert_eq!(grown.used, 100 + 2 * stats.capacity);
+    assert_eq!(grown.chunks, 2);
+    assert!(grown.wasted > 0);
+
+    arena.reset(ResetMode::RetainCapacity);
+    let retained = arena.stats();
+    assert_eq!(retained.used, 0);
+    assert_eq!(retained.chunks, 1);
+    assert!(retained.capacity >= grown.used);
+}
+
+#[test]
+fn retain_with_limit_from_stats() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    arena.alloc_slice_fill_with(64 * 1024, |_| 0u8);
+
+    let capacity = arena.stats().capacity;
+    arena.reset(ResetMode::RetainWithLimit(capacity / 2));
+    assert!(arena.stats().capacity < capacity);
+}
+
+#[test]
+fn rollback_restores_used_bytes() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    arena.alloc_str("kept");
+
+    let checkpoint = arena.checkpoint();
+    arena.alloc_slice_fill_with(4096, |i| i);
+    arena.rollback(checkpoint);
+
+    assert_eq!(arena.stats().used, 4);
+}
+
+#[test]
+fn rollback_keeps_resizes_of_older_allocations() {
+    let mut arena = ZigArenaSmpAllocator::new();
+    let layout = Layout::from_size_align(100, 8).unwrap();
+    let kept = unsafe { arena.alloc(layout) };
+    assert!(!kept.is_null());
+
+    let checkpoint = arena.checkpoint();
+    arena.alloc_slice_fill_with(16, |i| i as u8);
+
+    // Shrinking in place leaves the arena position where it is
+    let shrunk = unsafe { arena.realloc(kept, layout, 40) };
+    assert_eq!(shrunk, kept);
+    assert_eq!(arena.stats().used, 56);
+
+    arena.rollback(checkpoint);
+    assert_eq!(arena.stats().used, 40);
+}
@@ -0,0 +1,94 @@
+//! Sharing arenas across threads.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zigalloc::{ZigArenaSmpAllocator, ZigLocalArenaAllocator};
+
+const THREADS: usize = 8;
+const ALLOCATIONS: usize = 1000;
+
+fn assert_send_sync<T: Send + Sync>() {}
+fn assert_send<T: Send>() {}
+
+#[test]
+fn variants_are_send() {
+    assert_send_sync::<ZigArenaSmpAllocator>();
+    assert_send::<ZigLocalArenaAllocator>();
+}
+
+#[test]
+fn shared_arena_concurrent_allocations() {
+    let arena = ZigArenaSmpAllocator::new();
+
+    let slices: Vec<Vec<&mut [usize]>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let arena = &arena;
+                scope.spawn(move || {
+                    (0..ALLOCATIONS)
+                        .map(|i| arena.alloc_slice_fill_with(1 + i % 16, |_| thread))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    // Overlapping allocations would have overwritten each other
+    for (thread, thread_slices) in slices.iter().enumerate() {
+        for slice in thread_slices {
+            assert!(slice.iter().all(|&value| value == thread));
+        }
+    }
+
+    let expected: usize = (0..ALLOCATIONS).map(|i| 1 + i % 16).sum();
+    assert_eq!(
+        arena.stats().used,
+        THREADS * expected * std::mem::size_of::<usize>()
+    );
+}
+
+#[test]
+fn shared_arena_concurrent_drops() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let arena = ZigArenaSmpAllocator::new();
+    std::thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(|| {
+                for _ in 0..ALLOCATIONS {
+                    arena.alloc_with_drop(Counted);
+                }
+            });
+        }
+    });
+
+    drop(arena);
+    assert_eq!(DROPPED.load(Ordering::Relaxed), THREADS * ALLOCATIONS);
+}
+
+#[test]
+fn local_arena_moves_across_threads() {
+    let mut arena = ZigLocalArenaAllocator::new_local();
+    assert_eq!(arena.alloc_str("main"), "main");
+
+    arena = std::thread::spawn(move || {
+        assert_eq!(arena.alloc_str("worker"), "worker");
+        arena
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(arena.stats().used, "main".len() + "worker".len());
+}
@@ -0,0 +1,80 @@
+//! Typed, lifetime-bound allocations in the arena.
+
+use zigalloc::{ResetMode, ZigArenaSmpAllocator};
+
+#[derive(Debug, PartialEq)]
+enum Expr<'a> {
+    Number(i64),
+    Name(&'a str),
+    Add(&'a Expr<'a>, &'a Expr<'a>),
+}
+
+fn eval(expr: &Expr<'_>) -> i64 {
+    match expr {
+        Expr::Number(value) => *value,
+        Expr::Name(name) => name.len() as i64,
+        Expr::Add(lhs, rhs) => eval(lhs) + eval(rhs),
+    }
+}
+
+#[test]
+fn builds_a_tree_of_references() {
+    let arena = ZigArenaSmpAllocator::new();
+
+    let one = arena.alloc_value(Expr::Number(1));
+    let name = arena.alloc_value(Expr::Name(arena.alloc_str("four")));
+    let sum = arena.alloc_value(Expr::Add(one, name));
+
+    assert_eq!(eval(sum), 5);
+}
+
+#[test]
+fn alloc_returns_aligned_mutable_values() {
+    #[repr(align(64))]
+    struct Aligned(u8);
+
+    let arena = ZigArenaSmpAllocator::new();
+
+    let value = arena.alloc_value(41u64);
+    *value += 1;
+    assert_eq!(*value, 42);
+
+    let aligned = arena.alloc_value(Aligned(7));
+    assert_eq!(std::ptr::from_mut(aligned) as usize % 64, 0);
+    assert_eq!(aligned.0, 7);
+
+    // Zero-sized values don't need memory
+    let unit = arena.alloc_value(());
+    assert_eq!(*unit, ());
+}
+
+#[test]
+fn slices_and_strings() {
+    let arena = ZigArenaSmpAllocator::new();
+
+    let copied = arena.alloc_slice_copy(&[1u32, 2, 3]);
+    copied[0] = 10;
+    assert_eq!(copied, &[10, 2, 3]);
+
+    let squares = arena.alloc_slice_fill_with(5, |i| i * i);
+    assert_eq!(squares, &[0, 1, 4, 9, 16]);
+
+    let text = arena.alloc_str("hello");
+    text.make_ascii_uppercase();
+    assert_eq!(text, "HELLO");
+
+    assert!(arena.alloc_slice_copy::<u8>(&[]).is_empty());
+    assert_eq!(arena.alloc_str(""), "");
+}
+
+#[test]
+fn reset_after_borrows_end() {
+    let mut arena = ZigArenaSmpAllocator::new();
+
+    for round in 0..3 {
+        let numbers = arena.alloc_slice_fill_with(100, |i| i + round);
+        assert_eq!(numbers[99], 99 + round);
+
+        arena.reset(ResetMode::RetainCapacity);
+    }
+}
@@ -0,0 +1,98 @@
+use std::num::NonZeroUsize;
+use zigalloc::{
+    CreateError, DebugAllocatorConfig, FailingAllocatorConfig, MemoryPoolConfig,
+    SUPPORTED_STACK_TRACE_FRAMES, ZigArenaSmpAllocator, ZigDebugAllocator, ZigFailingAllocator,
+    ZigMemoryPool, ZigSmpAllocator,
+};
+
+#[test]
+fn try_new_creates_every_allocator() {
+    assert!(ZigSmpAllocator::try_new().is_ok());
+    assert!(ZigArenaSmpAllocator::try_new().is_ok());
+    assert!(ZigDebugAllocator::try_new().is_ok());
+}
+
+#[test]
+fn debug_rejects_unsupported_stack_trace_depth() {
+    let config = DebugAllocatorConfig {
+        stack_trace_frames: 33,
+        ..DebugAllocatorConfig::default()
+    };
+
+    assert_eq!(
+        ZigDebugAllocator::try_with_config(config).err(),
+        Some(CreateError::InvalidConfig)
+    );
+}
+
+#[test]
+fn debug_requires_unchecked_constructor_without_thread_safety() {
+    let config = DebugAllocatorConfig {
+        thread_safe: false,
+        ..DebugAllocatorConfig::default()
+    };
+
+    assert_eq!(
+        ZigDebugAllocator::try_with_config(config).err(),
+        Some(CreateError::InvalidConfig)
+    );
+
+    // Only used from this thread
+    let allocator = unsafe { ZigDebugAllocator::try_with_config_unchecked(config) };
+    assert!(allocator.is_ok());
+}
+
+#[test]
+fn debug_accepts_full_config() {
+    for &stack_trace_frames in &SUPPORTED_STACK_TRACE_FRAMES {
+        let config = DebugAllocatorConfig {
+            panic_on_leaks: true,
+            stack_trace_frames,
+            never_unmap: true,
+            retain_metadata: true,
+            verbose_log: false,
+            thread_safe: true,
+            memory_limit: NonZeroUsize::new(1 << 20),
+        };
+
+        assert!(ZigDebugAllocator::try_with_config(config).is_ok());
+    }
+}
+
+#[test]
+fn failing_rejects_invalid_probability() {
+    let config = FailingAllocatorConfig {
+        failure_probability: 2.0,
+        ..FailingAllocatorConfig::default()
+    };
+
+    assert_eq!(
+        ZigFailingAllocator::try_new(config).err(),
+        Some(CreateError::InvalidConfig)
+    );
+}
+
+#[test]
+fn pool_reports_out_of_memory_while_preheating() {
+    let backing = ZigFailingAllocator::new(FailingAllocatorConfig {
+        byte_limit: Some(0),
+        ..FailingAllocatorConfig::default()
+    });
+    let config = MemoryPoolConfig {
+        preheat: 16,
+        ..MemoryPoolConfig::default()
+    };
+
+    assert_eq!(
+        ZigMemoryPool::<u64, _>::try_with_backing(&backing, config).err(),
+        Some(CreateError::OutOfMemory)
+    );
+}
+
+#[test]
+fn create_errors_display() {
+    assert_eq!(
+        CreateError::UnknownStatus(7).to_string(),
+        "unknown status 7 while creating Zig allocator"
+    );
+}
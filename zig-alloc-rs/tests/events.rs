@@ -0,0 +1,101 @@
+//! Memory errors reported to the debug allocator event handler.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::{Arc, Mutex};
+use zigalloc::{DebugAllocatorConfig, DebugEventKind, ZigDebugAllocator};
+
+/// Configuration keeping freed allocations around, so double frees are told apart
+fn double_free_config() -> DebugAllocatorConfig {
+    DebugAllocatorConfig {
+        never_unmap: true,
+        retain_metadata: true,
+        ..DebugAllocatorConfig::default()
+    }
+}
+
+/// Allocator recording the kind of every event it reports
+fn recording_allocator() -> (ZigDebugAllocator, Arc<Mutex<Vec<DebugEventKind>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut allocator = ZigDebugAllocator::with_config(double_free_config());
+
+    let recorded = Arc::clone(&events);
+    allocator.set_event_handler(move |event| recorded.lock().unwrap().push(event.kind()));
+
+    (allocator, events)
+}
+
+#[test]
+fn reports_double_free() {
+    let (allocator, events) = recording_allocator();
+    let layout = Layout::from_size_align(16, 8).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        allocator.dealloc(ptr, layout);
+        allocator.dealloc(ptr, layout);
+    }
+
+    assert_eq!(*events.lock().unwrap(), [DebugEventKind::DoubleFree]);
+}
+
+#[test]
+fn reports_invalid_free() {
+    let (allocator, events) = recording_allocator();
+    let layout = Layout::from_size_align(64, 8).unwrap();
+
+    unsafe {
+        // Freed with the wrong size, the allocation is still released
+        let ptr = allocator.alloc(layout);
+        allocator.dealloc(ptr, Layout::from_size_align(32, 8).unwrap());
+    }
+
+    assert_eq!(*events.lock().unwrap(), [DebugEventKind::InvalidFree]);
+    assert!(allocator.live_allocations().unwrap().is_empty());
+}
+
+#[test]
+fn reports_leaks_on_drop() {
+    let (allocator, events) = recording_allocator();
+
+    let leaked = unsafe { allocator.alloc(Layout::from_size_align(32, 8).unwrap()) };
+    assert!(!leaked.is_null());
+
+    // Reported to the handler instead of panicking
+    drop(allocator);
+
+    assert_eq!(*events.lock().unwrap(), [DebugEventKind::Leak]);
+}
+
+#[test]
+fn handler_panics_are_kept_for_the_caller() {
+    let mut allocator = ZigDebugAllocator::with_config(double_free_config());
+    allocator.set_event_handler(|event| panic!("{event}"));
+
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        allocator.dealloc(ptr, layout);
+        allocator.dealloc(ptr, layout);
+    }
+
+    let payload = allocator.take_handler_panic().expect("handler panicked");
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        message.starts_with("double free of 16 byte(s)"),
+        "{message}"
+    );
+
+    assert!(allocator.take_handler_panic().is_none());
+}
+
+#[test]
+fn reports_free_of_unknown_memory() {
+    let (allocator, events) = recording_allocator();
+    let layout = Layout::from_size_align(16, 8).unwrap();
+
+    // Never handed out, ignored once reported
+    let mut local = [0u64; 2];
+    unsafe { allocator.dealloc(local.as_mut_ptr().cast(), layout) };
+
+    assert_eq!(*events.lock().unwrap(), [DebugEventKind::InvalidFree]);
+}
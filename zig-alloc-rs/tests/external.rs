@@ -0,0 +1,159 @@
+//! Rust allocators backing Zig allocators.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zigalloc::{
+    DebugAllocatorConfig, MemoryPoolConfig, ZigArenaAllocator, ZigDebugAllocator,
+    ZigExternalAllocator, ZigMemoryPool,
+};
+
+/// System allocator counting its live bytes
+#[derive(Clone, Default)]
+struct Counting {
+    live_bytes: Arc<AtomicUsize>,
+}
+
+impl Counting {
+    fn live_bytes(&self) -> usize {
+        self.live_bytes.load(Ordering::SeqCst)
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            self.live_bytes.fetch_add(layout.size(), Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        self.live_bytes.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn arena_over_rust_allocator() {
+    let counting = Counting::default();
+
+    {
+        let arena = ZigArenaAllocator::with_backing(ZigExternalAllocator::new(counting.clone()));
+        let values = arena.alloc_slice_fill_with(1000, |i| i);
+        assert_eq!(values[999], 999);
+
+        // The arena buffers come from the Rust allocator
+        assert_eq!(counting.live_bytes(), arena.stats().capacity);
+    }
+
+    assert_eq!(counting.live_bytes(), 0);
+}
+
+#[test]
+fn debug_allocator_over_rust_allocator() {
+    let counting = Counting::default();
+
+    {
+        let debug = ZigDebugAllocator::with_backing(
+            ZigExternalAllocator::new(counting.clone()),
+            DebugAllocatorConfig::default(),
+        );
+        let checkpoint = debug.checkpoint();
+
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let ptr = unsafe { debug.alloc(layout) };
+        assert!(!ptr.is_null());
+        assert!(counting.live_bytes() > 0);
+        assert!(debug.check_leaks_since(checkpoint).is_err());
+
+        unsafe { debug.dealloc(ptr, layout) };
+        debug.check_leaks_since(checkpoint).unwrap();
+    }
+
+    assert_eq!(counting.live_bytes(), 0);
+}
+
+#[test]
+fn debug_allocator_over_borrowed_backing() {
+    let counting = Counting::default();
+    let external = ZigExternalAllocator::new(counting.clone());
+    let layout = Layout::from_size_align(64, 8).unwrap();
+
+    {
+        let debug = ZigDebugAllocator::with_backing(&external, DebugAllocatorConfig::default());
+        let ptr = unsafe { debug.alloc(layout) };
+        assert!(!ptr.is_null());
+        assert!(counting.live_bytes() > 0);
+
+        unsafe { debug.dealloc(ptr, layout) };
+        debug.finish().unwrap();
+    }
+    assert_eq!(counting.live_bytes(), 0);
+
+    // Still usable once the debug allocator is gone
+    let ptr = unsafe { external.alloc(layout) };
+    assert!(!ptr.is_null());
+    unsafe { external.dealloc(ptr, layout) };
+}
+
+#[test]
+fn memory_pool_over_rust_allocator() {
+    let counting = Counting::default();
+
+    {
+        let pool = ZigMemoryPool::<[u64; 4], _>::with_backing(
+            ZigExternalAllocator::new(counting.clone()),
+            MemoryPoolConfig {
+                preheat: 16,
+                growable: false,
+            },
+        );
+        assert!(counting.live_bytes() >= 16 * 32);
+
+        let item = pool.create([7; 4]);
+        assert_eq!(*item, [7; 4]);
+        assert_eq!(pool.backing().inner().live_bytes(), counting.live_bytes());
+    }
+
+    assert_eq!(counting.live_bytes(), 0);
+}
+
+#[test]
+fn allocations_round_trip_through_zig() {
+    let allocator = ZigExternalAllocator::new(Counting::default());
+    let layout = Layout::from_size_align(16, 16).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert!(ptr.addr().is_multiple_of(16));
+        ptr.write_bytes(0xAB, 16);
+
+        // Zig never resizes in place, the Rust allocator moves the memory
+        let ptr = allocator.realloc(ptr, layout, 4096);
+        assert!(!ptr.is_null());
+        assert_eq!(*ptr.add(15), 0xAB);
+        assert_eq!(allocator.inner().live_bytes(), 4096);
+
+        allocator.dealloc(ptr, Layout::from_size_align(4096, 16).unwrap());
+    }
+
+    assert_eq!(allocator.inner().live_bytes(), 0);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn allocator_api_backing() {
+    use zigalloc::AllocatorAdapter;
+
+    let arena = ZigArenaAllocator::with_backing(ZigExternalAllocator::new(AllocatorAdapter(
+        std::alloc::Global,
+    )));
+
+    let mut values = Vec::new_in(&arena);
+    values.extend(0..1000);
+    assert_eq!(values.iter().sum::<i32>(), 499_500);
+}
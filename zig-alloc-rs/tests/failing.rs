@@ -0,0 +1,105 @@
+//! Fault injection with the failing allocator.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{
+    CreateError, FailingAllocatorConfig, ZigArenaAllocator, ZigDebugAllocator, ZigFailingAllocator,
+};
+
+const LAYOUT: Layout = match Layout::from_size_align(64, 8) {
+    Ok(layout) => layout,
+    Err(_) => unreachable!(),
+};
+
+/// Whether each of `count` allocations succeeded, freeing them right away
+fn attempt(allocator: &impl GlobalAlloc, count: usize) -> Vec<bool> {
+    (0..count)
+        .map(|_| {
+            let ptr = unsafe { allocator.alloc(LAYOUT) };
+            if !ptr.is_null() {
+                unsafe { allocator.dealloc(ptr, LAYOUT) };
+            }
+            !ptr.is_null()
+        })
+        .collect()
+}
+
+#[test]
+fn fails_from_the_given_allocation_on() {
+    let allocator = ZigFailingAllocator::new(FailingAllocatorConfig {
+        fail_index: Some(2),
+        ..Default::default()
+    });
+
+    assert_eq!(attempt(&allocator, 4), [true, true, false, false]);
+
+    let stats = allocator.stats();
+    assert_eq!(stats.allocations, 2);
+    assert_eq!(stats.deallocations, 2);
+    assert_eq!(stats.allocated_bytes, 2 * 64);
+    assert_eq!(stats.freed_bytes, 2 * 64);
+    assert_eq!(stats.failures, 2);
+}
+
+#[test]
+fn fails_past_byte_limit() {
+    let allocator = ZigFailingAllocator::new(FailingAllocatorConfig {
+        byte_limit: Some(200),
+        ..Default::default()
+    });
+
+    assert_eq!(attempt(&allocator, 4), [true, true, true, false]);
+}
+
+#[test]
+fn seeded_failures_are_reproducible() {
+    let config = FailingAllocatorConfig {
+        failure_probability: 0.5,
+        seed: 7,
+        ..Default::default()
+    };
+
+    let first = attempt(&ZigFailingAllocator::new(config), 100);
+    let second = attempt(&ZigFailingAllocator::new(config), 100);
+    assert_eq!(first, second);
+    assert!(first.contains(&true) && first.contains(&false));
+}
+
+#[test]
+fn rejects_invalid_probability() {
+    let result = ZigFailingAllocator::try_new(FailingAllocatorConfig {
+        failure_probability: f64::NAN,
+        ..Default::default()
+    });
+    assert!(matches!(result, Err(CreateError::InvalidConfig)));
+}
+
+#[test]
+fn arena_chunk_allocation_fails() {
+    let failing = ZigFailingAllocator::with_backing(
+        ZigDebugAllocator::new(),
+        FailingAllocatorConfig {
+            fail_index: Some(0),
+            ..Default::default()
+        },
+    );
+    let arena = ZigArenaAllocator::with_backing(&failing);
+
+    assert!(unsafe { arena.alloc(LAYOUT) }.is_null());
+    assert!(unsafe { arena.alloc(LAYOUT) }.is_null());
+    assert_eq!(failing.stats().failures, 2);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn try_reserve_error_path() {
+    let allocator = ZigFailingAllocator::new(FailingAllocatorConfig {
+        byte_limit: Some(1024),
+        ..Default::default()
+    });
+
+    let mut values: Vec<u8, _> = Vec::new_in(&allocator);
+    values.try_reserve(512).unwrap();
+    assert!(values.try_reserve(4096).is_err());
+    assert_eq!(values.capacity(), 512);
+}
@@ -0,0 +1,74 @@
+//! Allocating from a caller-provided buffer.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::mem::MaybeUninit;
+use zigalloc::{ZigArenaAllocator, ZigFixedBufferAllocator};
+
+#[test]
+fn allocations_stay_in_buffer() {
+    let mut buffer = [MaybeUninit::<u8>::uninit(); 256];
+    let range = buffer.as_ptr_range();
+    let allocator = ZigFixedBufferAllocator::new(&mut buffer);
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert!(!ptr.is_null());
+    assert!(range.contains(&ptr.cast_const().cast()));
+    assert_eq!(allocator.end_index() % 8, 0);
+    assert!(allocator.end_index() >= 64);
+
+    unsafe { allocator.dealloc(ptr, layout) };
+}
+
+#[test]
+fn fails_once_full_then_reset() {
+    let mut buffer = [MaybeUninit::<u8>::uninit(); 128];
+    let mut allocator = ZigFixedBufferAllocator::new(&mut buffer);
+
+    let layout = Layout::from_size_align(100, 1).unwrap();
+    let first = unsafe { allocator.alloc(layout) };
+    assert!(!first.is_null());
+    assert!(unsafe { allocator.alloc(layout) }.is_null());
+
+    allocator.reset();
+    assert_eq!(allocator.end_index(), 0);
+    assert!(!unsafe { allocator.alloc(layout) }.is_null());
+}
+
+#[test]
+fn last_allocation_is_freed() {
+    let mut buffer = [MaybeUninit::<u8>::uninit(); 128];
+    let allocator = ZigFixedBufferAllocator::new(&mut buffer);
+
+    let layout = Layout::from_size_align(32, 1).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert_eq!(allocator.end_index(), 32);
+
+    unsafe { allocator.dealloc(ptr, layout) };
+    assert_eq!(allocator.end_index(), 0);
+}
+
+#[test]
+fn arena_over_fixed_buffer() {
+    let mut buffer = [MaybeUninit::<u8>::uninit(); 8192];
+    let allocator = ZigFixedBufferAllocator::new(&mut buffer);
+
+    let arena = ZigArenaAllocator::with_backing(&allocator);
+    assert_eq!(arena.alloc_str("no heap"), "no heap");
+    assert!(allocator.end_index() > 0);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn vec_in_fixed_buffer() {
+    let mut buffer = [MaybeUninit::<u8>::uninit(); 1024];
+    let allocator = ZigFixedBufferAllocator::new(&mut buffer);
+
+    let mut values = Vec::with_capacity_in(16, &allocator);
+    values.extend(0..16u32);
+    assert_eq!(values.iter().sum::<u32>(), 120);
+
+    // Past the buffer, growing fails instead of going to the heap
+    assert!(values.try_reserve(1024).is_err());
+}
@@ -0,0 +1,69 @@
+//! Exit hook of `ZigGlobalDebugAllocator`.
+//!
+//! The hook runs as the process exits, so each test re-runs this binary
+//! with `CHILD_MODE` set and checks how the child process exits.
+
+use std::process::{Command, Output};
+use zigalloc::ZigGlobalDebugAllocator;
+
+#[global_allocator]
+static GLOBAL: ZigGlobalDebugAllocator = ZigGlobalDebugAllocator;
+
+/// Environment variable selecting what the `child` test does
+const CHILD_MODE: &str = "ZIGALLOC_EXIT_HOOK_CHILD";
+
+/// Run the `child` test alone in a new process
+fn run_child(mode: &str) -> Output {
+    Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "child", "--test-threads=1", "--nocapture"])
+        .env(CHILD_MODE, mode)
+        .output()
+        .unwrap()
+}
+
+#[inline(never)]
+fn leak_from_child() {
+    Box::leak(Box::new([0u8; 48]));
+}
+
+#[test]
+fn child() {
+    let Ok(mode) = std::env::var(CHILD_MODE) else {
+        return;
+    };
+
+    ZigGlobalDebugAllocator::install_exit_hook();
+
+    // Lazy std static, set up after the baseline and never freed
+    drop(std::io::stdin());
+    drop(vec![0u8; 256]);
+
+    if mode == "leak" {
+        leak_from_child();
+    }
+
+    std::process::exit(0);
+}
+
+#[test]
+fn exit_hook_ignores_std_statics() {
+    let output = run_child("clean");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn exit_hook_fails_on_leaks() {
+    let output = run_child("leak");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(1), "{stderr}");
+    assert!(
+        stderr.contains("1 allocation(s) leaked, 48 byte(s) in total"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,50 @@
+//! Leak reports of `ZigGlobalDebugAllocator`.
+//!
+//! Kept in its own test binary with a single test, since the global allocator
+//! sees every allocation of the process.
+
+use std::sync::OnceLock;
+use zigalloc::{LeakCheckError, ZigGlobalDebugAllocator};
+
+#[global_allocator]
+static GLOBAL: ZigGlobalDebugAllocator = ZigGlobalDebugAllocator;
+
+#[test]
+fn report_leaks_after_baseline() {
+    static LAZY: OnceLock<Vec<u8>> = OnceLock::new();
+
+    ZigGlobalDebugAllocator::mark_baseline();
+    assert!(ZigGlobalDebugAllocator::report_leaks().is_ok());
+
+    // Static-lifetime memory set up within a static scope isn't reported
+    let lazy = LAZY.get_or_init(|| ZigGlobalDebugAllocator::static_scope(|| vec![1; 64]));
+    let mut grown = ZigGlobalDebugAllocator::static_scope(|| {
+        let mut data = Vec::<u8>::with_capacity(8);
+        data.extend_from_slice(&[2; 4096]);
+        data
+    });
+    assert_eq!(lazy.len(), 64);
+    assert!(ZigGlobalDebugAllocator::report_leaks().is_ok());
+
+    // Still left out once grown outside the static scope
+    grown.extend_from_slice(&[3; 8192]);
+    assert!(ZigGlobalDebugAllocator::report_leaks().is_ok());
+
+    // Memory dropped before the check isn't reported either
+    drop(vec![0u8; 256]);
+    assert!(ZigGlobalDebugAllocator::report_leaks().is_ok());
+
+    let leaked = Box::leak(Box::new([0u8; 48]));
+    let Err(LeakCheckError::Leaked(report)) = ZigGlobalDebugAllocator::report_leaks() else {
+        panic!("leak not reported");
+    };
+
+    assert_eq!(report.allocations().len(), 1);
+    assert_eq!(report.allocations()[0].address, leaked.as_ptr() as usize);
+    assert_eq!(report.allocations()[0].size, 48);
+
+    grown.clear();
+    drop(unsafe { Box::from_raw(leaked) });
+    drop(report);
+    assert!(ZigGlobalDebugAllocator::report_leaks().is_ok());
+}
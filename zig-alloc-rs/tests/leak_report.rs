@@ -0,0 +1,134 @@
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{LeakCheckError, SymbolizeOptions, ZigDebugAllocator};
+
+#[test]
+fn finish_without_leaks() {
+    let allocator = ZigDebugAllocator::new();
+    let layout = Layout::from_size_align(64, 8).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        allocator.dealloc(ptr, layout);
+    }
+
+    assert!(allocator.finish().is_ok());
+}
+
+#[test]
+fn finish_reports_leaks() {
+    // Panicking on leaks proves `finish` takes over the reporting
+    let allocator = ZigDebugAllocator::with_panic_on_leaks(true);
+
+    let freed_layout = Layout::from_size_align(16, 1).unwrap();
+    let leaked_layout = Layout::from_size_align(500, 16).unwrap();
+
+    let leaked = unsafe {
+        let freed = allocator.alloc(freed_layout);
+        allocator.dealloc(freed, freed_layout);
+
+        allocator.alloc(leaked_layout)
+    };
+
+    let Err(LeakCheckError::Leaked(report)) = allocator.finish() else {
+        panic!("leak not reported");
+    };
+
+    assert_eq!(report.allocations().len(), 1);
+    assert_eq!(report.total_bytes(), 500);
+
+    let allocation = &report.allocations()[0];
+    assert_eq!(allocation.address, leaked as usize);
+    assert_eq!(allocation.size, 500);
+    assert_eq!(allocation.align, 16);
+    assert!(!allocation.return_addresses.is_empty());
+    assert!(allocation.return_addresses.len() <= 6);
+
+    let frames = allocation.symbolize(SymbolizeOptions::default());
+    assert!(
+        frames.iter().any(|frame| frame
+            .function
+            .as_deref()
+            .is_some_and(|function| function.ends_with("finish_reports_leaks"))),
+        "unexpected frames: {frames:?}"
+    );
+}
+
+#[test]
+fn live_allocations_keep_allocator_running() {
+    let allocator = ZigDebugAllocator::new();
+    let layout = Layout::from_size_align(32, 8).unwrap();
+
+    unsafe {
+        let first = allocator.alloc(layout);
+        let second = allocator.alloc(layout);
+
+        let live = allocator.live_allocations().unwrap();
+        assert_eq!(live.len(), 2);
+        assert_eq!(live[0].address, first as usize);
+        assert_eq!(live[1].address, second as usize);
+        assert!(live[0].sequence < live[1].sequence);
+        assert!(allocator.check_leaks().is_err());
+
+        allocator.dealloc(first, layout);
+        allocator.dealloc(second, layout);
+    }
+
+    assert!(allocator.check_leaks().is_ok());
+    assert!(allocator.finish().is_ok());
+}
+
+#[test]
+fn check_leaks_since_checkpoint() {
+    let allocator = ZigDebugAllocator::new();
+    let layout = Layout::from_size_align(128, 8).unwrap();
+
+    let long_lived = unsafe { allocator.alloc(layout) };
+
+    for request in 0..3 {
+        let checkpoint = allocator.checkpoint();
+
+        unsafe {
+            let scratch = allocator.alloc(layout);
+            allocator.dealloc(scratch, layout);
+        }
+        assert!(allocator.check_leaks_since(checkpoint).is_ok());
+
+        if request == 2 {
+            let leaked = unsafe { allocator.alloc(layout) };
+            let Err(LeakCheckError::Leaked(report)) = allocator.check_leaks_since(checkpoint)
+            else {
+                panic!("leak not reported");
+            };
+
+            assert_eq!(report.allocations().len(), 1);
+            assert_eq!(report.allocations()[0].address, leaked as usize);
+
+            unsafe { allocator.dealloc(leaked, layout) };
+        }
+    }
+
+    unsafe { allocator.dealloc(long_lived, layout) };
+    assert!(allocator.finish().is_ok());
+}
+
+#[test]
+fn moved_allocations_stay_before_checkpoint() {
+    let allocator = ZigDebugAllocator::new();
+    let layout = Layout::from_size_align(16, 8).unwrap();
+
+    let ptr = unsafe { allocator.alloc(layout) };
+    let checkpoint = allocator.checkpoint();
+
+    // Grown past its size class, the allocation moves
+    let moved = unsafe { allocator.realloc(ptr, layout, 5000) };
+    assert!(!moved.is_null());
+    assert!(allocator.check_leaks_since(checkpoint).is_ok());
+
+    let live = allocator.live_allocations().unwrap();
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].address, moved as usize);
+    assert_eq!(live[0].size, 5000);
+
+    unsafe { allocator.dealloc(moved, Layout::from_size_align(5000, 8).unwrap()) };
+    assert!(allocator.finish().is_ok());
+}
@@ -0,0 +1,58 @@
+//! Runtime memory limit of the debug allocator.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::ZigDebugAllocator;
+
+#[test]
+fn allocations_past_the_limit_fail() {
+    let allocator = ZigDebugAllocator::new();
+    let layout = Layout::from_size_align(1000, 8).unwrap();
+    let small = Layout::from_size_align(100, 8).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(allocator.total_requested_bytes(), 1000);
+
+        allocator.set_memory_limit(1024);
+        assert!(allocator.alloc(small).is_null());
+        assert_eq!(allocator.total_requested_bytes(), 1000);
+
+        allocator.clear_memory_limit();
+        let more = allocator.alloc(small);
+        assert!(!more.is_null());
+        assert_eq!(allocator.total_requested_bytes(), 1100);
+
+        allocator.dealloc(more, small);
+        allocator.dealloc(ptr, layout);
+    }
+
+    assert_eq!(allocator.total_requested_bytes(), 0);
+}
+
+#[test]
+fn oversized_allocation_fails() {
+    let allocator = ZigDebugAllocator::new();
+    allocator.set_memory_limit(4096);
+
+    unsafe {
+        let layout = Layout::from_size_align(8192, 8).unwrap();
+        assert!(allocator.alloc(layout).is_null());
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod allocator_api {
+    use zigalloc::ZigDebugAllocator;
+
+    #[test]
+    fn try_reserve_fails_past_the_limit() {
+        let allocator = ZigDebugAllocator::new();
+        allocator.set_memory_limit(1024);
+
+        let mut data = Vec::<u8, &ZigDebugAllocator>::new_in(&allocator);
+        assert!(data.try_reserve(512).is_ok());
+        assert!(data.try_reserve(4096).is_err());
+    }
+}
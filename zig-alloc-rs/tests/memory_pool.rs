@@ -0,0 +1,107 @@
+//! Typed memory pools.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zigalloc::{MemoryPoolConfig, ZigMemoryPool};
+
+struct Node {
+    value: u64,
+    next: Option<usize>,
+}
+
+#[test]
+fn freed_items_are_reused() {
+    let pool = ZigMemoryPool::<Node>::new();
+
+    let first = pool.create(Node {
+        value: 1,
+        next: None,
+    });
+    let address = std::ptr::from_ref::<Node>(&first).addr();
+    pool.destroy(first);
+
+    let second = pool.create(Node {
+        value: 2,
+        next: Some(1),
+    });
+    assert_eq!(std::ptr::from_ref::<Node>(&second).addr(), address);
+    assert_eq!(second.value, 2);
+    assert_eq!(second.next, Some(1));
+}
+
+#[test]
+fn items_are_aligned() {
+    #[repr(align(64))]
+    struct CacheLine([u8; 64]);
+
+    let pool = ZigMemoryPool::<CacheLine>::new();
+    let items: Vec<_> = (0..100).map(|_| pool.create(CacheLine([0; 64]))).collect();
+
+    for item in &items {
+        assert_eq!(std::ptr::from_ref::<CacheLine>(item).addr() % 64, 0);
+        assert_eq!(item.0[63], 0);
+    }
+}
+
+#[test]
+fn values_are_dropped() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let pool = ZigMemoryPool::new();
+    let first = pool.create(Counted);
+    let second = pool.create(Counted);
+
+    drop(first);
+    pool.destroy(second);
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn into_inner_moves_the_value_out() {
+    let pool = ZigMemoryPool::new();
+    let item = pool.create(String::from("pooled"));
+    assert_eq!(item.into_inner(), "pooled");
+}
+
+#[test]
+fn fixed_pool_is_exhausted() {
+    let mut pool = ZigMemoryPool::with_config(MemoryPoolConfig {
+        preheat: 2,
+        growable: false,
+    });
+
+    {
+        let _first = pool.create(1u32);
+        let second = pool.create(2u32);
+        assert_eq!(pool.try_create(3u32).err(), Some(3));
+
+        drop(second);
+        assert!(pool.try_create(4u32).is_ok());
+    }
+
+    std::mem::forget(pool.create(5u32));
+    std::mem::forget(pool.create(6u32));
+    assert!(pool.try_create(7u32).is_err());
+
+    // Resetting reclaims the leaked items
+    assert!(pool.reset());
+    assert!(pool.try_create(8u32).is_ok());
+}
+
+#[test]
+fn zero_sized_items() {
+    let pool = ZigMemoryPool::with_config(MemoryPoolConfig {
+        preheat: 0,
+        growable: false,
+    });
+
+    let items: Vec<_> = (0..10).map(|_| pool.create(())).collect();
+    assert_eq!(items.len(), 10);
+}
@@ -0,0 +1,75 @@
+//! Page and C allocators.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{
+    ZigArenaAllocator, ZigCAllocator, ZigGlobalCAllocator, ZigGlobalPageAllocator, ZigPageAllocator,
+};
+
+/// Allocate, grow and free through `allocator`, checking the contents survive
+fn round_trip(allocator: &impl GlobalAlloc, align: usize) {
+    let layout = Layout::from_size_align(100, align).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert!(ptr.addr().is_multiple_of(align));
+        ptr.write_bytes(0xAB, layout.size());
+
+        let ptr = allocator.realloc(ptr, layout, 100_000);
+        assert!(!ptr.is_null());
+        assert_eq!(*ptr.add(99), 0xAB);
+
+        allocator.dealloc(ptr, Layout::from_size_align(100_000, align).unwrap());
+    }
+}
+
+#[test]
+fn page_allocator_maps_pages() {
+    let allocator = ZigPageAllocator::new();
+    round_trip(&allocator, 64);
+
+    // Every allocation starts on a page boundary
+    let layout = Layout::new::<u8>();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert!(ptr.addr().is_multiple_of(4096));
+    unsafe { allocator.dealloc(ptr, layout) };
+}
+
+#[test]
+fn c_allocators() {
+    round_trip(&ZigCAllocator::new(), 8);
+    round_trip(&ZigCAllocator::new(), 64);
+    round_trip(&ZigCAllocator::raw(), 8);
+    round_trip(&ZigCAllocator::raw(), 64);
+}
+
+#[test]
+fn global_wrappers() {
+    round_trip(&ZigGlobalPageAllocator, 16);
+    round_trip(&ZigGlobalCAllocator, 16);
+}
+
+#[test]
+fn arena_over_page_allocator() {
+    let arena = ZigArenaAllocator::with_backing(ZigPageAllocator::new());
+    let values = arena.alloc_slice_fill_with(1000, |i| i);
+    assert_eq!(values[999], 999);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn collections() {
+    let page = ZigPageAllocator::new();
+    let mut large: Vec<u8, _> = Vec::with_capacity_in(1 << 20, &page);
+    large.resize(1 << 20, 1);
+    assert_eq!(
+        large.iter().map(|&byte| usize::from(byte)).sum::<usize>(),
+        1 << 20
+    );
+
+    let c = ZigCAllocator::new();
+    let mut small = Vec::new_in(&c);
+    small.extend(0..100);
+    assert_eq!(small.iter().sum::<i32>(), 4950);
+}
@@ -0,0 +1,178 @@
+//! Reallocation goes through `zig_ffi_allocator_realloc`, which resizes in place
+//! whenever the underlying Zig allocator allows it.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{ZigArenaSmpAllocator, ZigDebugAllocator, ZigSmpAllocator};
+
+/// Fill `len` bytes with a known pattern
+unsafe fn fill(ptr: *mut u8, len: usize) {
+    for i in 0..len {
+        unsafe { ptr.add(i).write(i as u8) };
+    }
+}
+
+/// Check the pattern written by `fill` is intact
+unsafe fn check(ptr: *const u8, len: usize) {
+    for i in 0..len {
+        assert_eq!(unsafe { ptr.add(i).read() }, i as u8);
+    }
+}
+
+#[test]
+fn smp_realloc_in_place_within_size_class() {
+    let allocator = ZigSmpAllocator::new();
+    let layout = Layout::from_size_align(100, 8).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        fill(ptr, 100);
+
+        // 100 and 120 bytes share the same SMP size class
+        let grown = allocator.realloc(ptr, layout, 120);
+        assert_eq!(grown, ptr);
+        check(grown, 100);
+
+        let grown_layout = Layout::from_size_align(120, 8).unwrap();
+        let shrunk = allocator.realloc(grown, grown_layout, 90);
+        assert_eq!(shrunk, ptr);
+        check(shrunk, 90);
+
+        allocator.dealloc(shrunk, Layout::from_size_align(90, 8).unwrap());
+    }
+}
+
+#[test]
+fn smp_realloc_moves_across_size_classes() {
+    let allocator = ZigSmpAllocator::new();
+    let layout = Layout::from_size_align(64, 8).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        fill(ptr, 64);
+
+        let grown = allocator.realloc(ptr, layout, 4096);
+        assert!(!grown.is_null());
+        check(grown, 64);
+
+        allocator.dealloc(grown, Layout::from_size_align(4096, 8).unwrap());
+    }
+}
+
+#[test]
+fn arena_realloc_extends_last_allocation_in_place() {
+    let allocator = ZigArenaSmpAllocator::new();
+    let layout = Layout::from_size_align(16, 8).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        fill(ptr, 16);
+
+        let grown = allocator.realloc(ptr, layout, 32);
+        assert_eq!(grown, ptr);
+        check(grown, 16);
+    }
+}
+
+#[test]
+fn debug_realloc_preserves_contents() {
+    let allocator = ZigDebugAllocator::new();
+    let layout = Layout::from_size_align(10, 1).unwrap();
+
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        fill(ptr, 10);
+
+        let grown = allocator.realloc(ptr, layout, 20_000);
+        assert!(!grown.is_null());
+        check(grown, 10);
+
+        allocator.dealloc(grown, Layout::from_size_align(20_000, 1).unwrap());
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod allocator_api {
+    use std::alloc::{Allocator, Layout};
+    use zigalloc::{ZigDebugAllocator, ZigSmpAllocator};
+
+    #[test]
+    fn vec_growth_round_trips() {
+        let allocator = ZigDebugAllocator::new();
+        let mut data = Vec::<u32, &ZigDebugAllocator>::new_in(&allocator);
+
+        for i in 0..10_000 {
+            data.push(i);
+        }
+        data.shrink_to_fit();
+
+        assert!(data.iter().copied().eq(0..10_000));
+    }
+
+    #[test]
+    fn grow_in_place_keeps_pointer() {
+        let allocator = ZigSmpAllocator::new();
+        let old_layout = Layout::from_size_align(100, 8).unwrap();
+        let new_layout = Layout::from_size_align(120, 8).unwrap();
+
+        let mem = allocator.allocate(old_layout).unwrap();
+        let grown = unsafe { allocator.grow(mem.cast(), old_layout, new_layout) }.unwrap();
+
+        assert_eq!(grown.cast::<u8>(), mem.cast::<u8>());
+        assert_eq!(grown.len(), 120);
+
+        unsafe { allocator.deallocate(grown.cast(), new_layout) };
+    }
+
+    #[test]
+    fn grow_zeroed_clears_new_bytes() {
+        let allocator = ZigSmpAllocator::new();
+        let old_layout = Layout::from_size_align(16, 8).unwrap();
+        let new_layout = Layout::from_size_align(4096, 8).unwrap();
+
+        let mem = allocator.allocate(old_layout).unwrap();
+        unsafe { mem.cast::<u8>().write_bytes(0xAB, 16) };
+
+        let grown = unsafe { allocator.grow_zeroed(mem.cast(), old_layout, new_layout) }.unwrap();
+        let bytes = unsafe { grown.as_ref() };
+
+        assert!(bytes[..16].iter().all(|&byte| byte == 0xAB));
+        assert!(bytes[16..].iter().all(|&byte| byte == 0));
+
+        unsafe { allocator.deallocate(grown.cast(), new_layout) };
+    }
+
+    #[test]
+    fn shrink_in_place_keeps_pointer() {
+        let allocator = ZigSmpAllocator::new();
+        let old_layout = Layout::from_size_align(120, 8).unwrap();
+        let new_layout = Layout::from_size_align(90, 8).unwrap();
+
+        let mem = allocator.allocate(old_layout).unwrap();
+        let shrunk = unsafe { allocator.shrink(mem.cast(), old_layout, new_layout) }.unwrap();
+
+        assert_eq!(shrunk.cast::<u8>(), mem.cast::<u8>());
+        assert_eq!(shrunk.len(), 90);
+
+        unsafe { allocator.deallocate(shrunk.cast(), new_layout) };
+    }
+
+    #[test]
+    fn shrink_to_zero_frees() {
+        let allocator = ZigDebugAllocator::new();
+        let old_layout = Layout::from_size_align(64, 8).unwrap();
+        let new_layout = Layout::from_size_align(0, 8).unwrap();
+
+        let mem = allocator.allocate(old_layout).unwrap();
+        let shrunk = unsafe { allocator.shrink(mem.cast(), old_layout, new_layout) }.unwrap();
+
+        assert_eq!(shrunk.len(), 0);
+        assert_eq!(shrunk.cast::<u8>().as_ptr() as usize % 8, 0);
+
+        // Dropping the debug allocator panics if the shrink leaked
+    }
+}
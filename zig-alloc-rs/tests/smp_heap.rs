@@ -0,0 +1,109 @@
+//! Isolated SMP heaps.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{ZigArenaAllocator, ZigSmpAllocator};
+
+const LAYOUT: Layout = match Layout::from_size_align(64, 8) {
+    Ok(layout) => layout,
+    Err(_) => unreachable!(),
+};
+
+#[test]
+fn heaps_are_isolated() {
+    let first = ZigSmpAllocator::isolated();
+    let second = ZigSmpAllocator::isolated();
+    assert!(first.is_isolated());
+    assert!(!ZigSmpAllocator::new().is_isolated());
+
+    unsafe {
+        let freed = first.alloc(LAYOUT);
+        first.dealloc(freed, LAYOUT);
+
+        // The slot freed in the first heap only comes back from the first heap
+        let other = second.alloc(LAYOUT);
+        assert_ne!(other, freed);
+        let reused = first.alloc(LAYOUT);
+        assert_eq!(reused, freed);
+
+        second.dealloc(other, LAYOUT);
+        first.dealloc(reused, LAYOUT);
+    }
+}
+
+#[test]
+fn mapped_bytes() {
+    assert_eq!(ZigSmpAllocator::new().mapped_bytes(), None);
+
+    let heap = ZigSmpAllocator::isolated();
+    assert_eq!(heap.mapped_bytes(), Some(0));
+
+    let small = unsafe { heap.alloc(LAYOUT) };
+    let slab_bytes = heap.mapped_bytes().unwrap();
+    assert!(slab_bytes > 0);
+
+    let large_layout = Layout::from_size_align(1 << 20, 8).unwrap();
+    let large = unsafe { heap.alloc(large_layout) };
+    assert_eq!(heap.mapped_bytes(), Some(slab_bytes + (1 << 20)));
+
+    unsafe {
+        heap.dealloc(large, large_layout);
+        heap.dealloc(small, LAYOUT);
+    }
+    assert_eq!(heap.mapped_bytes(), Some(slab_bytes));
+}
+
+#[test]
+fn drop_releases_live_allocations() {
+    let heap = ZigSmpAllocator::isolated();
+
+    for size in [16, 1024, 1 << 20] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let ptr = unsafe { heap.alloc(layout) };
+        assert!(!ptr.is_null());
+        unsafe { ptr.write_bytes(0xAB, size) };
+    }
+
+    // Everything goes back to the OS at once
+    drop(heap);
+}
+
+#[test]
+fn shared_across_threads() {
+    let heap = ZigSmpAllocator::isolated();
+
+    std::thread::scope(|scope| {
+        for seed in 0..4u8 {
+            let heap = &heap;
+            scope.spawn(move || {
+                for i in 0..1000 {
+                    let layout = Layout::from_size_align(1 + i % 512, 8).unwrap();
+                    unsafe {
+                        let ptr = heap.alloc(layout);
+                        assert!(!ptr.is_null());
+                        ptr.write_bytes(seed, layout.size());
+                        assert_eq!(*ptr.add(layout.size() - 1), seed);
+                        heap.dealloc(ptr, layout);
+                    }
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn arena_over_isolated_heap() {
+    let arena = ZigArenaAllocator::with_backing(ZigSmpAllocator::isolated());
+    let values = arena.alloc_slice_fill_with(1000, |i| i);
+    assert_eq!(values[999], 999);
+    assert!(arena.backing().mapped_bytes().unwrap() >= arena.stats().capacity);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn collections() {
+    let heap = ZigSmpAllocator::isolated();
+    let mut values = Vec::new_in(&heap);
+    values.extend(0..10_000);
+    assert_eq!(values.iter().sum::<i32>(), 49_995_000);
+}
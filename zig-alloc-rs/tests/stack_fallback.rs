@@ -0,0 +1,100 @@
+//! Inline buffer with a fallback allocator.
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{ZigDebugAllocator, ZigStackFallback};
+
+#[test]
+fn small_allocations_stay_inline() {
+    let stack = ZigStackFallback::<256, ZigDebugAllocator>::default();
+    let allocator = stack.allocator();
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert!(!ptr.is_null());
+    assert_eq!(ptr.addr() % 8, 0);
+    assert_eq!(stack.fallback().total_requested_bytes(), 0);
+
+    unsafe { allocator.dealloc(ptr, layout) };
+    assert_eq!(stack.end_index(), 0);
+}
+
+#[test]
+fn large_allocations_fall_back() {
+    let stack = ZigStackFallback::<128, ZigDebugAllocator>::default();
+    let allocator = stack.allocator();
+
+    let layout = Layout::from_size_align(1024, 8).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert!(!ptr.is_null());
+    assert_eq!(stack.end_index(), 0);
+    assert_eq!(stack.fallback().total_requested_bytes(), 1024);
+
+    unsafe { allocator.dealloc(ptr, layout) };
+    assert_eq!(stack.fallback().total_requested_bytes(), 0);
+}
+
+#[test]
+fn realloc_moves_to_fallback_when_full() {
+    let stack = ZigStackFallback::<128, ZigDebugAllocator>::default();
+    let allocator = stack.allocator();
+
+    let layout = Layout::from_size_align(32, 1).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    for i in 0..32 {
+        unsafe { ptr.add(i).write(i as u8) };
+    }
+
+    // Last allocation of the buffer, grown in place
+    let grown = unsafe { allocator.realloc(ptr, layout, 96) };
+    assert_eq!(grown, ptr);
+    assert_eq!(stack.end_index(), 96);
+
+    // No longer fits, copied to the fallback and given back to the buffer
+    let layout = Layout::from_size_align(96, 1).unwrap();
+    let moved = unsafe { allocator.realloc(grown, layout, 512) };
+    assert_ne!(moved, grown);
+    assert_eq!(stack.end_index(), 0);
+    assert_eq!(stack.fallback().total_requested_bytes(), 512);
+    for i in 0..32 {
+        assert_eq!(unsafe { moved.add(i).read() }, i as u8);
+    }
+
+    unsafe { allocator.dealloc(moved, Layout::from_size_align(512, 1).unwrap()) };
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn vec_spills_to_fallback() {
+    let stack = ZigStackFallback::<64, ZigDebugAllocator>::default();
+    let allocator = stack.allocator();
+
+    {
+        let mut values = Vec::new_in(allocator);
+        values.extend(0..8u32);
+        assert_eq!(stack.fallback().total_requested_bytes(), 0);
+
+        values.extend(8..64u32);
+        assert!(stack.fallback().total_requested_bytes() > 0);
+        assert_eq!(values.iter().sum::<u32>(), (0..64).sum());
+    }
+
+    assert_eq!(stack.fallback().total_requested_bytes(), 0);
+}
+
+#[test]
+fn moved_stack_uses_its_new_buffer() {
+    let stack = ZigStackFallback::<64, ZigDebugAllocator>::default();
+    let moved = Box::new(stack);
+
+    let start = std::ptr::from_ref(&*moved).addr();
+    let range = start..start + std::mem::size_of_val(&*moved);
+
+    let layout = Layout::from_size_align(16, 1).unwrap();
+    let allocator = moved.allocator();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert!(range.contains(&ptr.addr()));
+
+    unsafe { allocator.dealloc(ptr, layout) };
+    assert_eq!(moved.end_index(), 0);
+}
@@ -0,0 +1,94 @@
+//! Symbolization of the stack traces recorded by the debug allocator.
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{
+    DebugAllocatorConfig, LeakCheckError, SymbolizeOptions, ZigDebugAllocator, symbolize,
+};
+
+#[inline(never)]
+fn marker_function() -> usize {
+    std::hint::black_box(42)
+}
+
+#[inline(never)]
+fn leak_from_user_code(allocator: &ZigDebugAllocator) -> *mut u8 {
+    unsafe { allocator.alloc(Layout::from_size_align(24, 8).unwrap()) }
+}
+
+#[test]
+fn symbolize_resolves_function_names() {
+    assert_eq!(marker_function(), 42);
+
+    // Pretend to return right after the first instruction of the function
+    let frames = symbolize(
+        &[marker_function as *const () as usize + 1],
+        SymbolizeOptions::default(),
+    );
+
+    assert!(!frames.is_empty());
+    assert!(
+        frames[0]
+            .function
+            .as_deref()
+            .is_some_and(|function| function.ends_with("marker_function")),
+        "unexpected frames: {frames:?}"
+    );
+}
+
+#[test]
+fn symbolize_keeps_unresolved_addresses() {
+    let frames = symbolize(&[0x10], SymbolizeOptions::default());
+
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].address, 0x10);
+    assert_eq!(frames[0].function, None);
+}
+
+#[test]
+fn skip_allocator_frames_keeps_unresolved_addresses() {
+    // Stripped binaries resolve no names, their frames are kept as user code
+    let frames = symbolize(
+        &[0x10, 0x20],
+        SymbolizeOptions {
+            skip_allocator_frames: true,
+        },
+    );
+
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].address, 0x10);
+}
+
+#[test]
+fn leak_trace_starts_at_user_code() {
+    let allocator = ZigDebugAllocator::try_with_config(DebugAllocatorConfig {
+        stack_trace_frames: 16,
+        ..DebugAllocatorConfig::default()
+    })
+    .unwrap();
+
+    let leaked = leak_from_user_code(&allocator);
+    let Err(LeakCheckError::Leaked(report)) = allocator.finish() else {
+        panic!("leak not reported");
+    };
+
+    let leak = &report.allocations()[0];
+    assert_eq!(leak.address, leaked as usize);
+
+    let frames = leak.symbolize(SymbolizeOptions {
+        skip_allocator_frames: true,
+    });
+    assert!(
+        frames[0]
+            .function
+            .as_deref()
+            .is_some_and(|function| function.ends_with("leak_from_user_code")),
+        "unexpected frames: {frames:?}"
+    );
+
+    let displayed = report
+        .symbolized(SymbolizeOptions {
+            skip_allocator_frames: true,
+        })
+        .to_string();
+    assert!(displayed.contains("leak_from_user_code"));
+}
@@ -0,0 +1,108 @@
+//! Giving free memory back to the OS.
+
+use std::alloc::{GlobalAlloc, Layout};
+use zigalloc::{ZigGlobalSmpAllocator, ZigSmpAllocator};
+
+const LAYOUT: Layout = match Layout::from_size_align(256, 8) {
+    Ok(layout) => layout,
+    Err(_) => unreachable!(),
+};
+
+#[test]
+fn trim_unmaps_empty_slabs() {
+    let heap = ZigSmpAllocator::isolated();
+
+    unsafe {
+        let blocks: Vec<_> = (0..64).map(|_| heap.alloc(LAYOUT)).collect();
+        let mapped = heap.mapped_bytes().unwrap();
+        assert!(mapped > 0);
+
+        for block in blocks {
+            heap.dealloc(block, LAYOUT);
+        }
+
+        assert!(heap.trim().unwrap() >= mapped);
+        assert_eq!(heap.mapped_bytes(), Some(0));
+
+        // The heap maps memory again when needed
+        let block = heap.alloc(LAYOUT);
+        block.write_bytes(0xAB, LAYOUT.size());
+        heap.dealloc(block, LAYOUT);
+    }
+}
+
+#[test]
+fn trim_keeps_live_allocations() {
+    let heap = ZigSmpAllocator::isolated();
+
+    unsafe {
+        let kept = heap.alloc(LAYOUT);
+        kept.write_bytes(0xAB, LAYOUT.size());
+        let freed = heap.alloc(LAYOUT);
+        heap.dealloc(freed, LAYOUT);
+
+        // Slots smaller than a page keep their memory
+        assert_eq!(heap.trim(), Some(0));
+        assert!(heap.mapped_bytes().unwrap() > 0);
+        assert_eq!(*kept.add(LAYOUT.size() - 1), 0xAB);
+
+        heap.dealloc(kept, LAYOUT);
+    }
+}
+
+#[test]
+fn trim_counts_discarded_pages_once() {
+    let heap = ZigSmpAllocator::isolated();
+    let layout = Layout::from_size_align(16 * 1024, 8).unwrap();
+
+    unsafe {
+        let kept = heap.alloc(layout);
+        let freed = heap.alloc(layout);
+        heap.dealloc(freed, layout);
+
+        let discarded = heap.trim().unwrap();
+        assert_eq!(heap.trim(), Some(0));
+
+        // Reused and freed again, its pages count once more
+        let reused = heap.alloc(layout);
+        reused.write_bytes(0xAB, layout.size());
+        heap.dealloc(reused, layout);
+        assert_eq!(heap.trim(), Some(discarded));
+
+        heap.dealloc(kept, layout);
+    }
+}
+
+#[test]
+fn trim_empty_heap() {
+    let heap = ZigSmpAllocator::isolated();
+    assert_eq!(heap.trim(), Some(0));
+}
+
+#[test]
+fn shared_heap_is_not_trimmed() {
+    let allocator = ZigSmpAllocator::new();
+
+    unsafe {
+        let freed = allocator.alloc(LAYOUT);
+        allocator.dealloc(freed, LAYOUT);
+    }
+
+    assert_eq!(allocator.trim(), None);
+}
+
+#[test]
+fn global_heap_is_trimmed() {
+    // Only this test uses the global SMP heap, it isn't the global allocator here
+    unsafe {
+        let blocks: Vec<_> = (0..64)
+            .map(|_| ZigGlobalSmpAllocator.alloc(LAYOUT))
+            .collect();
+        for block in blocks {
+            ZigGlobalSmpAllocator.dealloc(block, LAYOUT);
+        }
+    }
+
+    assert!(ZigGlobalSmpAllocator::trim() > 0);
+    assert_eq!(ZigGlobalSmpAllocator::trim(), 0);
+}
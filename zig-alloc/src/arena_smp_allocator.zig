@@ -1,8 +1,8 @@
-//! Arena allocator backed by the SMP allocator.
+//! Arena allocator backed by the SMP allocator or any other `FfiAllocator`.
 //!
 //! This module provides an arena allocator that uses the thread-safe SMP allocator
-//! as its backing allocator. Arena allocators are useful for bulk memory management
-//! where all allocations can be freed at once.
+//! as its backing allocator by default. Arena allocators are useful for bulk memory
+//! management where all allocations can be freed at once.
 
 const std = @import("std");
 const ffi = @import("ffi.zig");
@@ -10,31 +10,479 @@ const ffi = @import("ffi.zig");
 /// Arena allocator that uses SMP allocator for backing memory.
 ///
 /// This allocator is ideal for scenarios where you need to allocate
-/// many objects and free them all at once.
+/// many objects and free them all at once. Another allocator created through
+/// the C ABI can back it instead, see `Config`.
 pub const ArenaSmpAllocator = struct {
     /// The underlying arena allocator
     arena: std.heap.ArenaAllocator,
 
-    /// Initialize a new arena allocator backed by the SMP allocator.
-    pub fn init() ArenaSmpAllocator {
+    /// Number of resets so far, checkpoints taken before a reset are stale
+    generation: usize = 0,
+
+    /// Record of the most recent checkpoint not rolled past yet
+    last_record: ?*CheckpointRecord = null,
+
+    /// Bytes handed out and not freed yet
+    used: usize = 0,
+
+    /// Serializes access to the arena when it is shared across threads
+    mutex: std.Thread.Mutex = .{},
+
+    /// Whether `mutex` needs to be taken
+    thread_safe: bool,
+
+    const vtable: std.mem.Allocator.VTable = .{
+        .alloc = alloc,
+        .resize = resize,
+        .remap = remap,
+        .free = free,
+    };
+
+    /// Configuration for creating arena allocators
+    pub const Config = extern struct {
+        /// `FfiAllocator` handle the buffers are reserved from, the SMP
+        /// allocator if null. It must outlive the arena.
+        backing_allocator: ?*anyopaque = null,
+
+        /// Whether the arena may be used from several threads at once.
+        /// Disabling it skips locking.
+        thread_safe: bool = true,
+    };
+
+    /// Initialize a new arena allocator over the configured backing allocator.
+    pub fn init(config: Config) ArenaSmpAllocator {
+        const backing = if (ffi.opaquePtrToFfiAllocator(config.backing_allocator)) |parent|
+            parent.allocator
+        else
+            std.heap.smp_allocator;
+
         return .{
-            .arena = std.heap.ArenaAllocator.init(std.heap.smp_allocator),
+            .arena = std.heap.ArenaAllocator.init(backing),
+            .thread_safe = config.thread_safe,
         };
     }
 
     /// Get the Zig allocator interface.
     pub fn allocator(self: *ArenaSmpAllocator) std.mem.Allocator {
-        return self.arena.allocator();
+        return .{
+            .ptr = self,
+            .vtable = &vtable,
+        };
+    }
+
+    /// Describe the memory held by the arena.
+    pub fn stats(self: *ArenaSmpAllocator) Stats {
+        self.lock();
+        defer self.unlock();
+
+        var reserved: usize = 0;
+        var chunks: usize = 0;
+
+        var it = self.arena.state.buffer_list.first;
+        while (it) |node| : (it = node.next) {
+            const buf_node: *BufNode = @fieldParentPtr("node", node);
+            reserved += buf_node.data;
+            chunks += 1;
+        }
+
+        // Room left in the buffer allocations are made from
+        const available = if (self.arena.state.buffer_list.first) |node|
+            @as(*BufNode, @fieldParentPtr("node", node)).data - @sizeOf(BufNode) - self.arena.state.end_index
+        else
+            0;
+
+        return .{
+            .capacity = reserved,
+            .used = self.used,
+            .chunks = chunks,
+            .wasted = reserved -| (chunks * @sizeOf(BufNode) + self.used + available),
+        };
+    }
+
+    /// Free everything allocated so far, keeping some capacity depending on `mode`.
+    ///
+    /// Returns false if the retained capacity could not be set up, the arena
+    /// is reset nonetheless.
+    pub fn reset(self: *ArenaSmpAllocator, mode: std.heap.ArenaAllocator.ResetMode) bool {
+        self.lock();
+        defer self.unlock();
+
+        // Retained buffers keep the records, which must not match their checkpoints anymore
+        self.invalidateRecordsAfter(null);
+
+        self.generation +%= 1;
+        self.used = 0;
+        return self.arena.reset(mode);
+    }
+
+    /// Current position in the arena, to later free what was allocated after it.
+    ///
+    /// A small record is allocated in the arena to recognize the checkpoint later on.
+    pub fn checkpoint(self: *ArenaSmpAllocator) std.mem.Allocator.Error!Checkpoint {
+        self.lock();
+        defer self.unlock();
+
+        const record = try self.arena.allocator().create(CheckpointRecord);
+        record.* = .{
+            .id = next_checkpoint_id.fetchAdd(1, .monotonic),
+            .prev = self.last_record,
+        };
+        self.last_record = record;
+
+        return .{
+            .record = record,
+            .id = record.id,
+            .generation = self.generation,
+        };
+    }
+
+    /// Whether `rollback` would accept `saved`, which has to come from this
+    /// arena without a reset or a rollback past it since.
+    pub fn canRollback(self: *ArenaSmpAllocator, saved: Checkpoint) bool {
+        self.lock();
+        defer self.unlock();
+
+        return self.canRollbackLocked(saved);
+    }
+
+    fn canRollbackLocked(self: *ArenaSmpAllocator, saved: Checkpoint) bool {
+        if (saved.generation != self.generation) {
+            return false;
+        }
+
+        // The record is only read once known to be in one of our buffers,
+        // memory of other arenas or already released is never touched.
+        const record = saved.record orelse return false;
+        if (self.bufferOf(@intFromPtr(record)) == null) {
+            return false;
+        }
+
+        // Records rolled past are zeroed, and ids are never reused
+        return record.id == saved.id;
+    }
+
+    /// Buffer of the arena holding `address`, if any.
+    fn bufferOf(self: *ArenaSmpAllocator, address: usize) ?*std.SinglyLinkedList.Node {
+        var it = self.arena.state.buffer_list.first;
+        while (it) |node| : (it = node.next) {
+            if (bufferHolds(node, address)) return node;
+        }
+        return null;
+    }
+
+    fn bufferHolds(node: *std.SinglyLinkedList.Node, address: usize) bool {
+        const buf_node: *BufNode = @fieldParentPtr("node", node);
+        const start = @intFromPtr(buf_node);
+        return address >= start and address < start + buf_node.data;
+    }
+
+    /// Zero the records of the checkpoints taken after `kept`, or of all of
+    /// them if null, so they are recognized as stale.
+    fn invalidateRecordsAfter(self: *ArenaSmpAllocator, kept: ?*CheckpointRecord) void {
+        while (self.last_record != kept) {
+            const record = self.last_record.?;
+            self.last_record = record.prev;
+            record.id = 0;
+        }
+    }
+
+    /// Free everything allocated after `saved`, releasing the buffers added since.
+    ///
+    /// Returns false, leaving the arena untouched, if `saved` is stale.
+    pub fn rollback(self: *ArenaSmpAllocator, saved: Checkpoint) bool {
+        self.lock();
+        defer self.unlock();
+
+        if (!self.canRollbackLocked(saved)) {
+            return false;
+        }
+
+        const record = saved.record.?;
+
+        // Allocations made before the checkpoint may have been resized or freed
+        // since, only the bytes still held after it are given back
+        var it = self.last_record;
+        while (it) |newer| : (it = newer.prev) {
+            self.used -= newer.used_after;
+            if (newer == record) break;
+        }
+        record.used_after = 0;
+        self.invalidateRecordsAfter(record);
+
+        const target = self.bufferOf(@intFromPtr(record)).?;
+
+        // Buffers are prepended, the ones before `target` were added after the checkpoint
+        while (self.arena.state.buffer_list.first != target) {
+            const node = self.arena.state.buffer_list.popFirst().?;
+            const buf_node: *BufNode = @fieldParentPtr("node", node);
+            const buffer = @as([*]u8, @ptrCast(buf_node))[0..buf_node.data];
+            self.arena.child_allocator.rawFree(buffer, .fromByteUnits(@alignOf(BufNode)), @returnAddress());
+        }
+
+        // Allocations resume right after the record
+        const target_buf_node: *BufNode = @fieldParentPtr("node", target);
+        self.arena.state.end_index = @intFromPtr(record) + @sizeOf(CheckpointRecord) - @intFromPtr(target_buf_node) - @sizeOf(BufNode);
+        return true;
+    }
+
+    /// Record of the most recent checkpoint taken before `address` was
+    /// allocated, null if it predates all of them.
+    fn recordBefore(self: *ArenaSmpAllocator, address: usize) ?*CheckpointRecord {
+        // Buffers and records are both listed newest first
+        var record = self.last_record;
+        var it = self.arena.state.buffer_list.first;
+        while (it) |node| : (it = node.next) {
+            const holds_address = bufferHolds(node, address);
+            while (record) |r| : (record = r.prev) {
+                if (!bufferHolds(node, @intFromPtr(r))) break;
+                if (holds_address and @intFromPtr(r) < address) return r;
+            }
+            if (holds_address) return record;
+        }
+        return null;
+    }
+
+    /// Account for `memory` now being `new_len` bytes long.
+    fn resized(self: *ArenaSmpAllocator, memory: []u8, new_len: usize) void {
+        self.used = self.used - memory.len + new_len;
+
+        if (self.last_record != null) {
+            if (self.recordBefore(@intFromPtr(memory.ptr))) |record| {
+                record.used_after = record.used_after - memory.len + new_len;
+            }
+        }
     }
 
     /// Deinitialize the arena, freeing all memory at once.
     pub fn deinit(self: *ArenaSmpAllocator) void {
         self.arena.deinit();
     }
+
+    fn lock(self: *ArenaSmpAllocator) void {
+        if (self.thread_safe) {
+            self.mutex.lock();
+        }
+    }
+
+    fn unlock(self: *ArenaSmpAllocator) void {
+        if (self.thread_safe) {
+            self.mutex.unlock();
+        }
+    }
+
+    fn alloc(ctx: *anyopaque, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        const self: *ArenaSmpAllocator = @ptrCast(@alignCast(ctx));
+        self.lock();
+        defer self.unlock();
+
+        const ptr = self.arena.allocator().rawAlloc(len, alignment, ret_addr) orelse return null;
+        self.used += len;
+        if (self.last_record) |record| {
+            record.used_after += len;
+        }
+        return ptr;
+    }
+
+    fn resize(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) bool {
+        const self: *ArenaSmpAllocator = @ptrCast(@alignCast(ctx));
+        self.lock();
+        defer self.unlock();
+
+        if (!self.arena.allocator().rawResize(memory, alignment, new_len, ret_addr)) {
+            return false;
+        }
+        self.resized(memory, new_len);
+        return true;
+    }
+
+    fn remap(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) ?[*]u8 {
+        const self: *ArenaSmpAllocator = @ptrCast(@alignCast(ctx));
+        self.lock();
+        defer self.unlock();
+
+        const new_ptr = self.arena.allocator().rawRemap(memory, alignment, new_len, ret_addr) orelse return null;
+        self.resized(memory, new_len);
+        return new_ptr;
+    }
+
+    fn free(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) void {
+        const self: *ArenaSmpAllocator = @ptrCast(@alignCast(ctx));
+        self.lock();
+        defer self.unlock();
+
+        self.resized(memory, 0);
+        self.arena.allocator().rawFree(memory, alignment, ret_addr);
+    }
 };
 
 /// Create a new `ArenaSmpAllocator`
-export fn zig_arena_smp_allocator_create() callconv(.c) ?*anyopaque {
-    const allocator = ffi.create(ArenaSmpAllocator) catch return null;
-    return @ptrCast(allocator);
+export fn zig_arena_smp_allocator_create(out_allocator: *?*anyopaque) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const allocator = ffi.createWithConfig(ArenaSmpAllocator, .{}) catch return .out_of_memory;
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
+
+/// Create a new `ArenaSmpAllocator` with the given configuration
+export fn zig_arena_smp_allocator_create_with_config(
+    config_ptr: ?*const ArenaSmpAllocator.Config,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const config = (config_ptr orelse return .invalid_config).*;
+
+    const allocator = ffi.createWithConfig(ArenaSmpAllocator, config) catch |err| return ffi.createStatus(err);
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
+
+/// Header at the start of every buffer of a `std.heap.ArenaAllocator`.
+///
+/// Mirrors the private `ArenaAllocator.BufNode`, `data` being the buffer length.
+const BufNode = struct {
+    data: usize,
+    node: std.SinglyLinkedList.Node = .{},
+};
+
+// Compile-time checks that `BufNode` and the arena state still match what
+// `std.heap.ArenaAllocator` is built on. Its `BufNode` can't be named, so
+// they pin the layout it is declared with: a `usize` followed by a list node.
+comptime {
+    const State = @FieldType(std.heap.ArenaAllocator, "state");
+    const Node = std.SinglyLinkedList.Node;
+
+    if (@FieldType(State, "buffer_list") != std.SinglyLinkedList or @FieldType(std.SinglyLinkedList, "first") != ?*Node) {
+        @compileError("ArenaAllocator.State.buffer_list is no longer a SinglyLinkedList of BufNode");
+    }
+
+    if (@FieldType(State, "end_index") != usize) {
+        @compileError("ArenaAllocator.State.end_index is no longer a usize");
+    }
+
+    const BufNode_ExpectedSize = @sizeOf(usize) + @sizeOf(Node);
+    const BufNode_ExpectedAlign = @max(@alignOf(usize), @alignOf(Node));
+
+    if (@sizeOf(BufNode) != BufNode_ExpectedSize) {
+        @compileError(std.fmt.comptimePrint(
+            "BufNode size mismatch - expected {d} bytes, got {d}",
+            .{ BufNode_ExpectedSize, @sizeOf(BufNode) },
+        ));
+    }
+
+    if (@alignOf(BufNode) != BufNode_ExpectedAlign) {
+        @compileError(std.fmt.comptimePrint(
+            "BufNode alignment mismatch - expected {d} byte alignment, got {d}",
+            .{ BufNode_ExpectedAlign, @alignOf(BufNode) },
+        ));
+    }
+
+    if (@offsetOf(BufNode, "data") != 0 or @offsetOf(BufNode, "node") != @sizeOf(usize)) {
+        @compileError(std.fmt.comptimePrint(
+            "BufNode field offsets mismatch - expected data at 0 and node at {d}, got {d} and {d}",
+            .{ @sizeOf(usize), @offsetOf(BufNode, "data"), @offsetOf(BufNode, "node") },
+        ));
+    }
+}
+
+/// Source of checkpoint ids, shared by all arenas so a checkpoint never
+/// matches a record of another arena
+var next_checkpoint_id: std.atomic.Value(u64) = .init(1);
+
+/// Record allocated in the arena by `ArenaSmpAllocator.checkpoint`, the
+/// position of the checkpoint being right after it
+const CheckpointRecord = extern struct {
+    /// Id of the checkpoint, zeroed once rolled past
+    id: u64,
+
+    /// Record of the previous checkpoint
+    prev: ?*CheckpointRecord,
+
+    /// Bytes handed out after the checkpoint and before the next one, not freed yet
+    used_after: usize = 0,
+};
+
+/// Position in an `ArenaSmpAllocator`, as reported through the C ABI
+pub const Checkpoint = extern struct {
+    /// Record allocated in the arena by the checkpoint
+    record: ?*CheckpointRecord,
+
+    /// Id the record has to hold for the checkpoint to be valid
+    id: u64,
+
+    /// Number of resets the arena went through
+    generation: usize,
+};
+
+/// Memory held by an `ArenaSmpAllocator`, as reported through the C ABI
+pub const Stats = extern struct {
+    /// Bytes reserved from the backing allocator, buffer headers included
+    capacity: usize,
+
+    /// Bytes handed out and not freed yet
+    used: usize,
+
+    /// Number of buffers reserved from the backing allocator
+    chunks: usize,
+
+    /// Bytes neither handed out nor available to the next allocations: tails
+    /// of older buffers, alignment padding, checkpoint records and freed
+    /// memory the arena can't reuse
+    wasted: usize,
+};
+
+/// How `zig_arena_smp_allocator_reset` handles the arena capacity
+pub const ResetModeTag = enum(c_int) {
+    /// Release every buffer back to the backing allocator
+    free_all = 0,
+
+    /// Keep all the capacity
+    retain_capacity = 1,
+
+    /// Keep at most `limit` bytes of capacity
+    retain_with_limit = 2,
+};
+
+/// Reset an `ArenaSmpAllocator`, freeing everything allocated so far
+export fn zig_arena_smp_allocator_reset(
+    allocator_ptr: ?*anyopaque,
+    mode: ResetModeTag,
+    limit: usize,
+) callconv(.c) bool {
+    const arena = ffi.opaquePtrToParent(ArenaSmpAllocator, allocator_ptr) orelse return false;
+
+    return arena.reset(switch (mode) {
+        .free_all => .free_all,
+        .retain_capacity => .retain_capacity,
+        .retain_with_limit => .{ .retain_with_limit = limit },
+    });
+}
+
+/// Get the current position in an `ArenaSmpAllocator`
+///
+/// Returns false if the checkpoint record could not be allocated.
+export fn zig_arena_smp_allocator_checkpoint(allocator_ptr: ?*anyopaque, out_checkpoint: *Checkpoint) callconv(.c) bool {
+    const arena = ffi.opaquePtrToParent(ArenaSmpAllocator, allocator_ptr) orelse return false;
+    out_checkpoint.* = arena.checkpoint() catch return false;
+    return true;
+}
+
+/// Check that an `ArenaSmpAllocator` can be rolled back to `checkpoint`
+export fn zig_arena_smp_allocator_can_rollback(allocator_ptr: ?*anyopaque, checkpoint: *const Checkpoint) callconv(.c) bool {
+    const arena = ffi.opaquePtrToParent(ArenaSmpAllocator, allocator_ptr) orelse return false;
+    return arena.canRollback(checkpoint.*);
+}
+
+/// Free everything allocated in an `ArenaSmpAllocator` after `checkpoint`
+export fn zig_arena_smp_allocator_rollback(allocator_ptr: ?*anyopaque, checkpoint: *const Checkpoint) callconv(.c) bool {
+    const arena = ffi.opaquePtrToParent(ArenaSmpAllocator, allocator_ptr) orelse return false;
+    return arena.rollback(checkpoint.*);
+}
+
+/// Describe the memory held by an `ArenaSmpAllocator`
+export fn zig_arena_smp_allocator_stats(allocator_ptr: ?*anyopaque, out_stats: *Stats) callconv(.c) bool {
+    const arena = ffi.opaquePtrToParent(ArenaSmpAllocator, allocator_ptr) orelse return false;
+    out_stats.* = arena.stats();
+    return true;
 }
@@ -0,0 +1,96 @@
+//! C allocator wrapper.
+//!
+//! This module provides a thin wrapper around Zig's allocators over the libc
+//! `malloc` family: `std.heap.c_allocator`, which resizes in place when
+//! `malloc_usable_size` allows it, and `std.heap.raw_c_allocator`, which
+//! calls libc as-is for alignments up to `max_align_t`.
+
+const std = @import("std");
+const ffi = @import("ffi.zig");
+
+/// Wrapper for the standard C allocators.
+///
+/// This is a zero-cost abstraction that provides the necessary
+/// interface for FFI compatibility.
+pub const CAllocator = struct {
+    /// Whether to call libc as-is
+    raw: bool,
+
+    const raw_vtable: std.mem.Allocator.VTable = .{
+        .alloc = rawAlloc,
+        .resize = rawResize,
+        .remap = rawRemap,
+        .free = rawFree,
+    };
+
+    /// Configuration for creating C allocators
+    pub const Config = extern struct {
+        /// Use `std.heap.raw_c_allocator` instead of `std.heap.c_allocator`,
+        /// except for alignments larger than `max_align_t`
+        raw: bool = false,
+    };
+
+    /// Initialize a new C allocator instance.
+    pub fn init(config: Config) CAllocator {
+        return .{ .raw = config.raw };
+    }
+
+    /// Get the Zig allocator interface.
+    pub fn allocator(self: *CAllocator) std.mem.Allocator {
+        if (!self.raw) {
+            return std.heap.c_allocator;
+        }
+        return .{
+            .ptr = self,
+            .vtable = &raw_vtable,
+        };
+    }
+
+    /// Deinitialize the allocator (no-op for the C allocators).
+    pub fn deinit(self: *CAllocator) void {
+        _ = self;
+    }
+
+    /// Allocator serving `alignment` in raw mode: `raw_c_allocator` only
+    /// supports alignments up to `max_align_t`, `c_allocator` takes the others.
+    fn rawFor(alignment: std.mem.Alignment) std.mem.Allocator {
+        return if (alignment.compare(.lte, .of(std.c.max_align_t)))
+            std.heap.raw_c_allocator
+        else
+            std.heap.c_allocator;
+    }
+
+    fn rawAlloc(ctx: *anyopaque, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        _ = ctx;
+        return rawFor(alignment).rawAlloc(len, alignment, ret_addr);
+    }
+
+    fn rawResize(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) bool {
+        _ = ctx;
+        return rawFor(alignment).rawResize(memory, alignment, new_len, ret_addr);
+    }
+
+    fn rawRemap(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) ?[*]u8 {
+        _ = ctx;
+        return rawFor(alignment).rawRemap(memory, alignment, new_len, ret_addr);
+    }
+
+    fn rawFree(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) void {
+        _ = ctx;
+        rawFor(alignment).rawFree(memory, alignment, ret_addr);
+    }
+};
+
+/// Create a new `CAllocator`
+export fn zig_c_allocator_create(
+    config_ptr: ?*const CAllocator.Config,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const config = (config_ptr orelse return .invalid_config).*;
+
+    const allocator = ffi.createWithConfig(CAllocator, config) catch |err| return ffi.createStatus(err);
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
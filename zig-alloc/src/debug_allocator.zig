@@ -7,57 +7,294 @@
 const std = @import("std");
 const ffi = @import("ffi.zig");
 
-/// Default configuration for the debug allocator with sensible safety settings.
-const DebugAllocatorConfig = std.heap.DebugAllocatorConfig{
-    .stack_trace_frames = if (std.debug.sys_can_stack_trace) 6 else 0,
+/// Stack trace depths the debug allocator is compiled with.
+///
+/// `std.heap.DebugAllocator` is configured at comptime, so one variant is
+/// compiled in for every combination of these depths and the boolean options
+/// of `DebugAllocator.Config` but `thread_safe`, then picked at runtime.
+/// Requested depths are rounded up to the nearest entry.
+pub const stack_trace_frame_variants = [_]usize{ 0, 6, 16, 32 };
+
+/// Deepest stack trace that can be requested.
+pub const max_stack_trace_frames = stack_trace_frame_variants[stack_trace_frame_variants.len - 1];
+
+/// Number of stack frames captured per allocation by default.
+pub const default_stack_trace_frames = 6;
+
+/// Comptime options selecting one compiled-in variant.
+const VariantOptions = struct {
+    stack_trace_frames: usize,
+    never_unmap: bool,
+    retain_metadata: bool,
+    verbose_log: bool,
+};
+
+/// Configuration of a debug allocator variant with sensible safety settings.
+fn variantConfig(comptime options: VariantOptions) std.heap.DebugAllocatorConfig {
+    return .{
+        .stack_trace_frames = if (std.debug.sys_can_stack_trace) options.stack_trace_frames else 0,
+
+        .safety = true,
+        // Calls are serialized by the wrapper lock, which also guards `live`.
+        .thread_safe = false,
+        .never_unmap = options.never_unmap,
+        .retain_metadata = options.retain_metadata,
+        .backing_allocator_zeroes = true,
+        .canary = @truncate(0x9232a6ff85dff10f),
+
+        // Always compiled in, disabled at runtime by leaving the limit at its maximum.
+        .enable_memory_limit = true,
 
-    .safety = true,
-    .thread_safe = true,
-    .backing_allocator_zeroes = true,
-    .canary = @truncate(0x9232a6ff85dff10f),
+        // Enables emitting info messages with the size and address of every allocation.
+        .verbose_log = options.verbose_log,
+    };
+}
+
+/// Type-erased operations on one `std.heap.DebugAllocator` instantiation.
+const Variant = struct {
+    /// Heap-allocate and initialize the debug allocator over a backing allocator
+    create: *const fn (std.mem.Allocator) std.mem.Allocator.Error!*anyopaque,
+
+    /// Deinitialize and free the debug allocator, reporting leaks
+    destroy: *const fn (*anyopaque) std.heap.Check,
 
-    // Enables emitting info messages with the size and address of every allocation.
-    .verbose_log = false,
+    /// Get the Zig allocator interface
+    allocator: *const fn (*anyopaque) std.mem.Allocator,
+
+    /// Set the maximum number of bytes that may be requested
+    set_memory_limit: *const fn (*anyopaque, usize) void,
+
+    /// Get the number of bytes currently requested
+    total_requested_bytes: *const fn (*anyopaque) usize,
 };
 
+fn VariantImpl(comptime options: VariantOptions) type {
+    return struct {
+        const Inner = std.heap.DebugAllocator(variantConfig(options));
+
+        const variant: Variant = .{
+            .create = create,
+            .destroy = destroy,
+            .allocator = allocator,
+            .set_memory_limit = setMemoryLimit,
+            .total_requested_bytes = totalRequestedBytes,
+        };
+
+        fn create(backing: std.mem.Allocator) std.mem.Allocator.Error!*anyopaque {
+            const inner = try std.heap.c_allocator.create(Inner);
+            inner.* = .{ .backing_allocator = backing };
+            return inner;
+        }
+
+        fn destroy(ptr: *anyopaque) std.heap.Check {
+            const inner: *Inner = @ptrCast(@alignCast(ptr));
+            const status = inner.deinit();
+            std.heap.c_allocator.destroy(inner);
+            return status;
+        }
+
+        fn allocator(ptr: *anyopaque) std.mem.Allocator {
+            const inner: *Inner = @ptrCast(@alignCast(ptr));
+            return inner.allocator();
+        }
+
+        fn setMemoryLimit(ptr: *anyopaque, limit: usize) void {
+            const inner: *Inner = @ptrCast(@alignCast(ptr));
+            inner.requested_memory_limit = limit;
+        }
+
+        fn totalRequestedBytes(ptr: *anyopaque) usize {
+            const inner: *Inner = @ptrCast(@alignCast(ptr));
+            return inner.total_requested_bytes;
+        }
+    };
+}
+
+/// Pick the compiled-in variant matching `config`.
+///
+/// Returns null if the requested stack trace depth is not supported.
+fn selectVariant(config: DebugAllocator.Config) ?*const Variant {
+    inline for (stack_trace_frame_variants) |stack_trace_frames| {
+        if (config.stack_trace_frames <= stack_trace_frames) {
+            return selectVariantWithFrames(stack_trace_frames, config);
+        }
+    }
+    return null;
+}
+
+fn selectVariantWithFrames(comptime stack_trace_frames: usize, config: DebugAllocator.Config) *const Variant {
+    inline for (.{ false, true }) |never_unmap| {
+        inline for (.{ false, true }) |retain_metadata| {
+            inline for (.{ false, true }) |verbose_log| {
+                if (config.never_unmap == never_unmap and
+                    config.retain_metadata == retain_metadata and
+                    config.verbose_log == verbose_log)
+                {
+                    return &VariantImpl(.{
+                        .stack_trace_frames = stack_trace_frames,
+                        .never_unmap = never_unmap,
+                        .retain_metadata = retain_metadata,
+                        .verbose_log = verbose_log,
+                    }).variant;
+                }
+            }
+        }
+    }
+    unreachable;
+}
+
 /// Debug allocator wrapper with configurable leak detection.
 ///
 /// This allocator provides memory safety features including:
 /// - Leak detection
-/// - Buffer overflow detection (via canary values)
-/// - Thread safety
+/// - Double free and invalid free detection
+/// - Optional thread safety
 /// - Optional stack traces for allocations
+/// - Optional memory limit
 pub const DebugAllocator = struct {
     /// Configuration options for `DebugAllocator.init`.
     pub const Config = struct {
         /// Whether to panic when the allocator is de-initialized and leaks are found.
         panic_on_leaks: bool = true,
+
+        /// Number of stack frames captured per allocation, rounded up to
+        /// the nearest entry of `stack_trace_frame_variants`.
+        stack_trace_frames: usize = default_stack_trace_frames,
+
+        /// Never return freed pages to the backing allocator.
+        never_unmap: bool = false,
+
+        /// Keep metadata of freed allocations to report double frees precisely.
+        retain_metadata: bool = false,
+
+        /// Log the size and address of every allocation.
+        verbose_log: bool = false,
+
+        /// Whether the allocator may be used from several threads at once.
+        thread_safe: bool = true,
+
+        /// Maximum number of bytes that may be requested at once, or null for no limit.
+        memory_limit: ?usize = null,
+
+        /// `FfiAllocator` handle the pages are requested from, the page
+        /// allocator if null. It must outlive the debug allocator.
+        backing_allocator: ?*anyopaque = null,
     };
 
-    /// Debug allocator
-    debug_allocator: std.heap.DebugAllocator(DebugAllocatorConfig),
+    /// Variant operations matching `inner`
+    variant: *const Variant,
+
+    /// Type-erased `std.heap.DebugAllocator`
+    inner: *anyopaque,
+
+    /// Allocator interface of `inner`
+    inner_allocator: std.mem.Allocator,
+
+    /// Allocations handed out and not freed yet, keyed by address
+    live: std.AutoHashMapUnmanaged(usize, Tracked) = .empty,
+
+    /// Allocations freed since their address was last handed out, kept
+    /// with `retain_metadata` to tell double frees apart
+    freed: std.AutoHashMapUnmanaged(usize, Tracked) = .empty,
+
+    /// Whether to fill `freed`
+    retain_metadata: bool,
+
+    /// Protects `live` and calls into `inner`
+    mutex: std.Thread.Mutex = .{},
+
+    /// Whether `mutex` needs to be taken
+    thread_safe: bool,
+
+    /// Number of stack frames captured per allocation
+    stack_trace_frames: usize,
+
+    /// Sequence number of the most recent allocation
+    last_sequence: u64 = 0,
+
+    /// Called on memory errors instead of panicking or logging
+    event_callback: ?EventCallback = null,
+
+    /// Context passed to `event_callback`
+    event_context: ?*anyopaque = null,
 
     /// Whether to panic when the allocator is de-initialized
     /// and we find memory leaks
     panic_on_exit_leaks: bool,
 
+    /// Set once the leaks have been handed over with `takeLeaks`
+    leaks_taken: bool = false,
+
+    const vtable: std.mem.Allocator.VTable = .{
+        .alloc = alloc,
+        .resize = resize,
+        .remap = remap,
+        .free = free,
+    };
+
     /// Initialize a debug allocator with the given configuration.
-    pub fn init(config: Config) DebugAllocator {
+    pub fn init(config: Config) ffi.CreateError!DebugAllocator {
+        const variant = selectVariant(config) orelse return error.InvalidConfig;
+        const backing = if (ffi.opaquePtrToFfiAllocator(config.backing_allocator)) |parent|
+            parent.allocator
+        else
+            std.heap.page_allocator;
+        const inner = try variant.create(backing);
+
+        if (config.memory_limit) |limit| {
+            variant.set_memory_limit(inner, limit);
+        }
+
         return .{
-            .debug_allocator = .{ .backing_allocator = std.heap.page_allocator },
+            .variant = variant,
+            .inner = inner,
+            .inner_allocator = variant.allocator(inner),
+            .thread_safe = config.thread_safe,
+            .stack_trace_frames = config.stack_trace_frames,
+            .retain_metadata = config.retain_metadata,
             .panic_on_exit_leaks = config.panic_on_leaks,
         };
     }
 
     /// Get the Zig allocator interface.
     pub fn allocator(self: *DebugAllocator) std.mem.Allocator {
-        return self.debug_allocator.allocator();
+        return .{
+            .ptr = self,
+            .vtable = &vtable,
+        };
     }
 
     /// Deinitialize the allocator and check for memory leaks.
-    /// Will panic or print warnings if leaks are detected based on configuration.
+    /// Will panic or print warnings if leaks are detected based on configuration,
+    /// or report them to the event callback if one is set.
     pub fn deinit(self: *DebugAllocator) void {
-        const deinit_status = self.debug_allocator.deinit();
+        var reported = self.leaks_taken;
+        if (!reported) {
+            if (self.event_callback) |callback| {
+                var it = self.live.iterator();
+                while (it.next()) |entry| {
+                    if (entry.value_ptr.sequence == static_sequence) continue;
+
+                    const event = leakEvent(liveAllocation(entry.key_ptr.*, entry.value_ptr));
+                    callback(&event, self.event_context);
+                }
+                reported = true;
+            }
+        }
+
+        // Leaks handed over through `takeLeaks` or to the event callback were
+        // already reported, release them so the inner allocator doesn't report them again.
+        if (reported) {
+            var it = self.live.iterator();
+            while (it.next()) |entry| {
+                const memory = @as([*]u8, @ptrFromInt(entry.key_ptr.*))[0..entry.value_ptr.size];
+                self.inner_allocator.rawFree(memory, .fromByteUnits(entry.value_ptr.alignment), @returnAddress());
+            }
+        }
+        self.live.deinit(std.heap.c_allocator);
+        self.freed.deinit(std.heap.c_allocator);
+
+        const deinit_status = self.variant.destroy(self.inner);
 
         if (deinit_status == .leak) {
             if (self.panic_on_exit_leaks) {
@@ -67,18 +304,487 @@ pub const DebugAllocator = struct {
             }
         }
     }
+
+    /// Report memory errors to `callback` instead of panicking or logging,
+    /// or restore the default behavior if null.
+    ///
+    /// Faulty calls are then not passed on to `std.heap.DebugAllocator`:
+    /// frees with the wrong size or alignment release the allocation as it
+    /// was made, frees of unknown memory are ignored, and resizes fail.
+    /// `callback` is called without the allocator lock held, so it may allocate.
+    pub fn setEventCallback(self: *DebugAllocator, callback: ?EventCallback, context: ?*anyopaque) void {
+        self.lock();
+        defer self.unlock();
+
+        self.event_callback = callback;
+        self.event_context = context;
+    }
+
+    /// Set the maximum number of bytes that may be requested at once, or null
+    /// for no limit.
+    ///
+    /// Allocations that would go past the limit fail, memory already handed
+    /// out is left untouched.
+    pub fn setMemoryLimit(self: *DebugAllocator, limit: ?usize) void {
+        self.lock();
+        defer self.unlock();
+
+        self.variant.set_memory_limit(self.inner, limit orelse std.math.maxInt(usize));
+    }
+
+    /// Number of bytes currently requested and not freed yet.
+    pub fn totalRequestedBytes(self: *DebugAllocator) usize {
+        self.lock();
+        defer self.unlock();
+
+        return self.variant.total_requested_bytes(self.inner);
+    }
+
+    /// Sequence number of the most recent allocation.
+    ///
+    /// Passing it to `liveAllocations` later on lists only what was allocated
+    /// in between.
+    pub fn checkpoint(self: *DebugAllocator) u64 {
+        self.lock();
+        defer self.unlock();
+
+        return self.last_sequence;
+    }
+
+    /// Copy every live allocation made after the `since` checkpoint into a
+    /// buffer allocated with `std.heap.c_allocator`, sorted by address.
+    ///
+    /// The allocator keeps running, pass 0 to list all live allocations.
+    pub fn liveAllocations(self: *DebugAllocator, since: u64) std.mem.Allocator.Error![]LiveAllocation {
+        self.lock();
+        defer self.unlock();
+
+        var count: usize = 0;
+        var count_it = self.live.valueIterator();
+        while (count_it.next()) |tracked| {
+            if (tracked.sequence > since) count += 1;
+        }
+
+        const allocations = try std.heap.c_allocator.alloc(LiveAllocation, count);
+
+        var it = self.live.iterator();
+        var i: usize = 0;
+        while (it.next()) |entry| {
+            if (entry.value_ptr.sequence <= since) continue;
+
+            allocations[i] = liveAllocation(entry.key_ptr.*, entry.value_ptr);
+            i += 1;
+        }
+        std.mem.sortUnstable(LiveAllocation, allocations, {}, addressLessThan);
+
+        return allocations;
+    }
+
+    /// Exclude a live allocation from leak checks, for memory meant to live
+    /// until the process exits.
+    ///
+    /// Returns false if `address` isn't a live allocation.
+    pub fn markStatic(self: *DebugAllocator, address: usize) bool {
+        self.lock();
+        defer self.unlock();
+
+        const tracked = self.live.getPtr(address) orelse return false;
+        tracked.sequence = static_sequence;
+        return true;
+    }
+
+    /// Same as `liveAllocations(0)`, but hands the leaks over to the caller
+    /// so that `deinit` doesn't report them again.
+    pub fn takeLeaks(self: *DebugAllocator) std.mem.Allocator.Error![]LiveAllocation {
+        const allocations = try self.liveAllocations(0);
+        self.leaks_taken = true;
+        return allocations;
+    }
+
+    fn lock(self: *DebugAllocator) void {
+        if (self.thread_safe) {
+            self.mutex.lock();
+        }
+    }
+
+    fn unlock(self: *DebugAllocator) void {
+        if (self.thread_safe) {
+            self.mutex.unlock();
+        }
+    }
+
+    /// Look up the allocation `memory` is freed or resized as.
+    ///
+    /// Unless it matches a live allocation, the error is left in `report`
+    /// if there is an event callback to report it to.
+    fn verify(self: *DebugAllocator, report: *?Report, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) Verdict {
+        const address = @intFromPtr(memory.ptr);
+        const live = self.live.getPtr(address);
+        const verdict: Verdict = blk: {
+            const tracked = live orelse break :blk .unknown;
+            if (tracked.size != memory.len or tracked.alignment != alignment.toByteUnits()) break :blk .mismatched;
+            break :blk .valid;
+        };
+
+        if (verdict == .valid) return verdict;
+        const callback = self.event_callback orelse return verdict;
+
+        var event: Event = .{
+            .kind = .invalid_free,
+            .allocation = std.mem.zeroes(LiveAllocation),
+            .address = address,
+            .size = memory.len,
+            .alignment = alignment.toByteUnits(),
+            .trace_len = 0,
+            .trace = @splat(0),
+        };
+        if (live) |tracked| {
+            event.allocation = liveAllocation(address, tracked);
+        } else if (self.freed.getPtr(address)) |tracked| {
+            // Described as it was before the first free
+            event.kind = .double_free;
+            event.allocation = liveAllocation(address, tracked);
+        }
+        event.trace_len = self.captureTrace(&event.trace, ret_addr);
+
+        report.* = .{ .callback = callback, .context = self.event_context, .event = event };
+        return verdict;
+    }
+
+    /// Capture the stack trace leading to `ret_addr`, returning its length.
+    fn captureTrace(self: *DebugAllocator, trace: *[max_stack_trace_frames]usize, ret_addr: usize) usize {
+        if (!std.debug.sys_can_stack_trace or self.stack_trace_frames == 0) return 0;
+
+        const addresses = trace[0..@min(self.stack_trace_frames, max_stack_trace_frames)];
+        const stack_trace = std.debug.captureCurrentStackTrace(.{ .first_address = ret_addr }, addresses);
+        return @min(stack_trace.index, addresses.len);
+    }
+
+    /// Keep a freed allocation around to report double frees.
+    ///
+    /// Best effort, a double free of an allocation that couldn't be kept is
+    /// reported as an invalid free.
+    fn retire(self: *DebugAllocator, address: usize, tracked: Tracked) void {
+        if (!self.retain_metadata) return;
+        self.freed.put(std.heap.c_allocator, address, tracked) catch {};
+    }
+
+    /// Describe a new allocation, along with the stack trace leading to `ret_addr`.
+    fn track(self: *DebugAllocator, size: usize, alignment: std.mem.Alignment, ret_addr: usize) Tracked {
+        self.last_sequence += 1;
+
+        var tracked: Tracked = .{
+            .sequence = self.last_sequence,
+            .size = size,
+            .alignment = alignment.toByteUnits(),
+        };
+        tracked.trace_len = self.captureTrace(&tracked.trace, ret_addr);
+        return tracked;
+    }
+
+    fn alloc(ctx: *anyopaque, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        const self: *DebugAllocator = @ptrCast(@alignCast(ctx));
+        self.lock();
+        defer self.unlock();
+
+        // Reserve the bookkeeping first, so a tracked allocation never has to be rolled back.
+        self.live.ensureUnusedCapacity(std.heap.c_allocator, 1) catch return null;
+
+        const ptr = self.inner_allocator.rawAlloc(len, alignment, ret_addr) orelse return null;
+        self.live.putAssumeCapacity(@intFromPtr(ptr), self.track(len, alignment, ret_addr));
+        _ = self.freed.remove(@intFromPtr(ptr));
+
+        return ptr;
+    }
+
+    fn resize(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) bool {
+        const self: *DebugAllocator = @ptrCast(@alignCast(ctx));
+
+        // Declared before locking, so the callback runs once unlocked
+        var report: ?Report = null;
+        defer if (report) |*r| r.dispatch();
+
+        self.lock();
+        defer self.unlock();
+
+        if (self.verify(&report, memory, alignment, ret_addr) != .valid and report != null) {
+            return false;
+        }
+
+        if (!self.inner_allocator.rawResize(memory, alignment, new_len, ret_addr)) {
+            return false;
+        }
+
+        if (self.live.getPtr(@intFromPtr(memory.ptr))) |tracked| tracked.size = new_len;
+        return true;
+    }
+
+    fn remap(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) ?[*]u8 {
+        const self: *DebugAllocator = @ptrCast(@alignCast(ctx));
+
+        // Declared before locking, so the callback runs once unlocked
+        var report: ?Report = null;
+        defer if (report) |*r| r.dispatch();
+
+        self.lock();
+        defer self.unlock();
+
+        // Reserved before moving the entry, so it can always be put back
+        self.live.ensureUnusedCapacity(std.heap.c_allocator, 1) catch return null;
+
+        if (self.verify(&report, memory, alignment, ret_addr) != .valid and report != null) {
+            return null;
+        }
+
+        const address = @intFromPtr(memory.ptr);
+        const tracked = self.live.get(address);
+        const new_ptr = self.inner_allocator.rawRemap(memory, alignment, new_len, ret_addr) orelse
+            self.move(address, tracked orelse return null, alignment, new_len, ret_addr) orelse
+            return null;
+
+        var moved = (self.live.fetchRemove(address) orelse return new_ptr).value;
+        if (@intFromPtr(new_ptr) != address) self.retire(address, moved);
+        moved.size = new_len;
+        self.live.putAssumeCapacity(@intFromPtr(new_ptr), moved);
+
+        return new_ptr;
+    }
+
+    /// Move a live allocation the inner allocator can't remap in place.
+    ///
+    /// Done here rather than by the caller, so the allocation keeps its
+    /// sequence number and static status.
+    fn move(self: *DebugAllocator, address: usize, tracked: Tracked, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) ?[*]u8 {
+        const new_ptr = self.inner_allocator.rawAlloc(new_len, alignment, ret_addr) orelse return null;
+
+        const old_memory = @as([*]u8, @ptrFromInt(address))[0..tracked.size];
+        @memcpy(new_ptr[0..@min(old_memory.len, new_len)], old_memory[0..@min(old_memory.len, new_len)]);
+        self.inner_allocator.rawFree(old_memory, .fromByteUnits(tracked.alignment), ret_addr);
+
+        return new_ptr;
+    }
+
+    fn free(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) void {
+        const self: *DebugAllocator = @ptrCast(@alignCast(ctx));
+
+        // Declared before locking, so the callback runs once unlocked
+        var report: ?Report = null;
+        defer if (report) |*r| r.dispatch();
+
+        self.lock();
+        defer self.unlock();
+
+        const address = @intFromPtr(memory.ptr);
+        var freed_memory = memory;
+        var freed_alignment = alignment;
+        switch (self.verify(&report, memory, alignment, ret_addr)) {
+            .valid => {},
+            // Once reported, released as it was allocated rather than leaked
+            .mismatched => if (report != null) {
+                const tracked = self.live.getPtr(address).?;
+                freed_memory = memory.ptr[0..tracked.size];
+                freed_alignment = .fromByteUnits(tracked.alignment);
+            },
+            // Already freed or never handed out, nothing to release
+            .unknown => if (report != null) return,
+        }
+
+        // Untracked only once the inner allocator released the memory, which
+        // it doesn't for frees it rejects
+        const requested_bytes = self.variant.total_requested_bytes(self.inner);
+        self.inner_allocator.rawFree(freed_memory, freed_alignment, ret_addr);
+        if (self.variant.total_requested_bytes(self.inner) < requested_bytes) {
+            if (self.live.fetchRemove(address)) |entry| self.retire(address, entry.value);
+        }
+    }
+};
+
+/// Outcome of looking up the allocation freed or resized by a call
+const Verdict = enum {
+    /// Live allocation with the same size and alignment
+    valid,
+
+    /// Live allocation with another size or alignment
+    mismatched,
+
+    /// Already freed, or never handed out
+    unknown,
+};
+
+/// Memory error of a call, dispatched once the allocator lock is released
+const Report = struct {
+    callback: EventCallback,
+    context: ?*anyopaque,
+    event: Event,
+
+    fn dispatch(self: *const Report) void {
+        self.callback(&self.event, self.context);
+    }
+};
+
+/// What the wrapper keeps about a live allocation
+const Tracked = struct {
+    /// Increasing number identifying when the allocation was made
+    sequence: u64,
+
+    /// Size in bytes
+    size: usize,
+
+    /// Alignment in bytes
+    alignment: usize,
+
+    /// Number of valid entries in `trace`
+    trace_len: usize = 0,
+
+    /// Return addresses leading to the allocation, innermost first
+    trace: [max_stack_trace_frames]usize = @splat(0),
+};
+
+/// Describe the allocation at `address` through the C ABI
+fn liveAllocation(address: usize, tracked: *const Tracked) LiveAllocation {
+    return .{
+        .sequence = tracked.sequence,
+        .address = address,
+        .size = tracked.size,
+        .alignment = tracked.alignment,
+        .trace_len = tracked.trace_len,
+        .trace = tracked.trace,
+    };
+}
+
+fn addressLessThan(_: void, a: LiveAllocation, b: LiveAllocation) bool {
+    return a.address < b.address;
+}
+
+/// Event reporting an allocation that was never freed
+fn leakEvent(live_allocation: LiveAllocation) Event {
+    return .{
+        .kind = .leak,
+        .allocation = live_allocation,
+        .address = live_allocation.address,
+        .size = live_allocation.size,
+        .alignment = live_allocation.alignment,
+        .trace_len = 0,
+        .trace = @splat(0),
+    };
+}
+
+/// Kind of memory error reported to an `EventCallback`
+pub const EventKind = enum(c_int) {
+    /// Allocation never freed, reported when the allocator is destroyed
+    leak = 0,
+
+    /// Allocation freed twice
+    double_free = 1,
+
+    /// Free or resize of an allocation with the wrong size or alignment
+    invalid_free = 2,
+};
+
+/// Memory error reported to an `EventCallback`
+pub const Event = extern struct {
+    /// What went wrong
+    kind: EventKind,
+
+    /// Allocation concerned, zeroed if the address was never handed out
+    allocation: LiveAllocation,
+
+    /// Address passed by the faulty call, or of the leaked allocation
+    address: usize,
+
+    /// Size passed by the faulty call, or of the leaked allocation
+    size: usize,
+
+    /// Alignment passed by the faulty call, or of the leaked allocation
+    alignment: usize,
+
+    /// Number of valid entries in `trace`
+    trace_len: usize,
+
+    /// Return addresses leading to the faulty call, innermost first, empty for leaks
+    trace: [max_stack_trace_frames]usize,
+};
+
+/// Function called with every memory error of a `DebugAllocator`
+pub const EventCallback = *const fn (event: *const Event, context: ?*anyopaque) callconv(.c) void;
+
+/// Sequence number of allocations excluded from leak checks,
+/// below every checkpoint so `liveAllocations` always skips them
+const static_sequence: u64 = 0;
+
+/// Allocation that hasn't been freed yet, as reported through the C ABI
+pub const LiveAllocation = extern struct {
+    /// Increasing number identifying when the allocation was made
+    sequence: u64,
+
+    /// Address of the allocation
+    address: usize,
+
+    /// Size in bytes
+    size: usize,
+
+    /// Alignment in bytes
+    alignment: usize,
+
+    /// Number of valid entries in `trace`
+    trace_len: usize,
+
+    /// Return addresses leading to the allocation, innermost first
+    trace: [max_stack_trace_frames]usize,
 };
 
+// Compile-time checks to ensure FFI compatibility
+comptime {
+    const LiveAllocation_ExpectedSize = @sizeOf(u64) + (4 + max_stack_trace_frames) * @sizeOf(usize);
+
+    if (@sizeOf(LiveAllocation) != LiveAllocation_ExpectedSize) {
+        @compileError(std.fmt.comptimePrint(
+            "LiveAllocation size mismatch - expected {d} bytes, got {d}",
+            .{ LiveAllocation_ExpectedSize, @sizeOf(LiveAllocation) },
+        ));
+    }
+}
+
+// Compile-time checks to ensure FFI compatibility
+comptime {
+    const Event_ExpectedSize = @alignOf(LiveAllocation) + @sizeOf(LiveAllocation) + (4 + max_stack_trace_frames) * @sizeOf(usize);
+
+    if (@sizeOf(Event) != Event_ExpectedSize) {
+        @compileError(std.fmt.comptimePrint(
+            "Event size mismatch - expected {d} bytes, got {d}",
+            .{ Event_ExpectedSize, @sizeOf(Event) },
+        ));
+    }
+}
+
 /// Configuration for creating debug allocators via FFI
 pub const DebugAllocatorCreateConfig = extern struct {
     /// Whether to panic when leaks are detected on deinit
     panic_on_leaks: bool,
+
+    /// Number of stack frames captured per allocation
+    stack_trace_frames: u8,
+
+    /// Never return freed pages to the backing allocator
+    never_unmap: bool,
+
+    /// Keep metadata of freed allocations
+    retain_metadata: bool,
+
+    /// Log the size and address of every allocation
+    verbose_log: bool,
+
+    /// Whether the allocator may be used from several threads at once
+    thread_safe: bool,
+
+    /// Maximum number of bytes that may be requested at once, 0 for no limit
+    memory_limit: usize,
 };
 
 // Compile-time checks to ensure FFI compatibility
 comptime {
-    const DebugAllocatorCreateConfig_ExpectedSize = 1;
-    const DebugAllocatorCreateConfig_ExpectedAlign = 1;
+    const DebugAllocatorCreateConfig_ExpectedSize = 8 + @sizeOf(usize);
+    const DebugAllocatorCreateConfig_ExpectedAlign = @alignOf(usize);
 
     // Verify struct size and alignment match expected C layout
     if (@sizeOf(DebugAllocatorCreateConfig) != DebugAllocatorCreateConfig_ExpectedSize) {
@@ -94,15 +800,132 @@ comptime {
             .{ DebugAllocatorCreateConfig_ExpectedAlign, @alignOf(DebugAllocatorCreateConfig) },
         ));
     }
+
+    if (@offsetOf(DebugAllocatorCreateConfig, "memory_limit") != 8) {
+        @compileError(std.fmt.comptimePrint(
+            "DebugAllocatorCreateConfig.memory_limit offset mismatch - expected 8, got {d}",
+            .{@offsetOf(DebugAllocatorCreateConfig, "memory_limit")},
+        ));
+    }
 }
 
 /// Create a new `DebugAllocator` with the given configuration
-export fn zig_debug_allocator_create(config_ptr: ?*const DebugAllocatorCreateConfig) callconv(.c) ?*anyopaque {
-    const config = (config_ptr orelse return null).*;
+export fn zig_debug_allocator_create(
+    config_ptr: ?*const DebugAllocatorCreateConfig,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    return zig_debug_allocator_create_with_backing(config_ptr, null, out_allocator);
+}
+
+/// Create a new `DebugAllocator` requesting its pages from another `FfiAllocator`
+///
+/// A null `backing_allocator` falls back to the page allocator. The backing
+/// allocator must outlive the debug allocator.
+export fn zig_debug_allocator_create_with_backing(
+    config_ptr: ?*const DebugAllocatorCreateConfig,
+    backing_allocator: ?*anyopaque,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const config = (config_ptr orelse return .invalid_config).*;
 
     const allocator = ffi.createWithConfig(DebugAllocator, .{
         .panic_on_leaks = config.panic_on_leaks,
-    }) catch return null;
+        .stack_trace_frames = config.stack_trace_frames,
+        .never_unmap = config.never_unmap,
+        .retain_metadata = config.retain_metadata,
+        .verbose_log = config.verbose_log,
+        .thread_safe = config.thread_safe,
+        .memory_limit = if (config.memory_limit == 0) null else config.memory_limit,
+        .backing_allocator = backing_allocator,
+    }) catch |err| return ffi.createStatus(err);
+
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
+
+/// Hand the leaks of a `DebugAllocator` over to the caller
+///
+/// The returned buffer must be released with `zig_debug_allocator_free_allocations`.
+/// Leaks taken this way are no longer reported when the allocator is destroyed.
+export fn zig_debug_allocator_take_leaks(
+    allocator_ptr: ?*anyopaque,
+    out_allocations: *?[*]LiveAllocation,
+    out_len: *usize,
+) callconv(.c) bool {
+    out_allocations.* = null;
+    out_len.* = 0;
+
+    const debug_allocator = ffi.opaquePtrToParent(DebugAllocator, allocator_ptr) orelse return false;
+    const allocations = debug_allocator.takeLeaks() catch return false;
+
+    out_allocations.* = allocations.ptr;
+    out_len.* = allocations.len;
+    return true;
+}
+
+/// Get the sequence number of the most recent allocation of a `DebugAllocator`
+export fn zig_debug_allocator_checkpoint(allocator_ptr: ?*anyopaque) callconv(.c) u64 {
+    const debug_allocator = ffi.opaquePtrToParent(DebugAllocator, allocator_ptr) orelse return 0;
+    return debug_allocator.checkpoint();
+}
+
+/// List the allocations of a `DebugAllocator` made after the `since` checkpoint
+///
+/// The allocator keeps running. The returned buffer must be released with
+/// `zig_debug_allocator_free_allocations`.
+export fn zig_debug_allocator_live_allocations(
+    allocator_ptr: ?*anyopaque,
+    since: u64,
+    out_allocations: *?[*]LiveAllocation,
+    out_len: *usize,
+) callconv(.c) bool {
+    out_allocations.* = null;
+    out_len.* = 0;
+
+    const debug_allocator = ffi.opaquePtrToParent(DebugAllocator, allocator_ptr) orelse return false;
+    const allocations = debug_allocator.liveAllocations(since) catch return false;
+
+    out_allocations.* = allocations.ptr;
+    out_len.* = allocations.len;
+    return true;
+}
+
+/// Report the memory errors of a `DebugAllocator` to `callback`, or restore
+/// the default behavior if null
+export fn zig_debug_allocator_set_event_callback(
+    allocator_ptr: ?*anyopaque,
+    callback: ?EventCallback,
+    context: ?*anyopaque,
+) callconv(.c) void {
+    const debug_allocator = ffi.opaquePtrToParent(DebugAllocator, allocator_ptr) orelse return;
+    debug_allocator.setEventCallback(callback, context);
+}
+
+/// Set the memory limit of a `DebugAllocator`, `maxInt(usize)` for no limit
+export fn zig_debug_allocator_set_memory_limit(allocator_ptr: ?*anyopaque, limit: usize) callconv(.c) void {
+    const debug_allocator = ffi.opaquePtrToParent(DebugAllocator, allocator_ptr) orelse return;
+    debug_allocator.setMemoryLimit(limit);
+}
+
+/// Get the number of bytes currently requested from a `DebugAllocator`
+export fn zig_debug_allocator_total_requested_bytes(allocator_ptr: ?*anyopaque) callconv(.c) usize {
+    const debug_allocator = ffi.opaquePtrToParent(DebugAllocator, allocator_ptr) orelse return 0;
+    return debug_allocator.totalRequestedBytes();
+}
+
+/// Exclude a live allocation of a `DebugAllocator` from leak checks
+export fn zig_debug_allocator_mark_static(allocator_ptr: ?*anyopaque, address: usize) callconv(.c) bool {
+    const debug_allocator = ffi.opaquePtrToParent(DebugAllocator, allocator_ptr) orelse return false;
+    return debug_allocator.markStatic(address);
+}
 
-    return @ptrCast(allocator);
+/// Free a buffer returned by one of the `zig_debug_allocator_*` exports
+export fn zig_debug_allocator_free_allocations(
+    allocations: ?[*]LiveAllocation,
+    len: usize,
+) callconv(.c) void {
+    const ptr = allocations orelse return;
+    std.heap.c_allocator.free(ptr[0..len]);
 }
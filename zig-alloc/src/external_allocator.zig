@@ -0,0 +1,112 @@
+//! Allocator implemented outside of Zig.
+//!
+//! This module wraps C function pointers into a `std.mem.Allocator`, so an
+//! allocator of the host language can back the other allocators of this
+//! library, for example as the `backing_allocator` of an arena.
+
+const std = @import("std");
+const ffi = @import("ffi.zig");
+
+/// Allocate `len` bytes aligned to `alignment`, or return null
+pub const AllocFn = *const fn (context: ?*anyopaque, len: usize, alignment: usize) callconv(.c) ?[*]u8;
+
+/// Move `memory` to a block of `new_len` bytes, or return null and leave it untouched
+pub const ReallocFn = *const fn (context: ?*anyopaque, memory: [*]u8, len: usize, alignment: usize, new_len: usize) callconv(.c) ?[*]u8;
+
+/// Free `memory`, allocated with the same length and alignment
+pub const FreeFn = *const fn (context: ?*anyopaque, memory: [*]u8, len: usize, alignment: usize) callconv(.c) void;
+
+/// `std.mem.Allocator` calling into functions of the host language.
+///
+/// Memory is never resized in place: the host allocator frees with the
+/// length it allocated, so growing and shrinking go through `realloc`.
+pub const ExternalAllocator = struct {
+    /// Passed to every function
+    context: ?*anyopaque,
+
+    alloc_fn: AllocFn,
+    realloc_fn: ReallocFn,
+    free_fn: FreeFn,
+
+    const vtable: std.mem.Allocator.VTable = .{
+        .alloc = alloc,
+        .resize = resize,
+        .remap = remap,
+        .free = free,
+    };
+
+    /// Configuration for creating external allocators
+    pub const Config = extern struct {
+        /// Passed to every function, it must outlive the allocator
+        context: ?*anyopaque = null,
+
+        alloc: ?AllocFn = null,
+        realloc: ?ReallocFn = null,
+        free: ?FreeFn = null,
+    };
+
+    /// Initialize a new external allocator, all functions are required.
+    pub fn init(config: Config) ffi.CreateError!ExternalAllocator {
+        return .{
+            .context = config.context,
+            .alloc_fn = config.alloc orelse return error.InvalidConfig,
+            .realloc_fn = config.realloc orelse return error.InvalidConfig,
+            .free_fn = config.free orelse return error.InvalidConfig,
+        };
+    }
+
+    /// Get the Zig allocator interface.
+    pub fn allocator(self: *ExternalAllocator) std.mem.Allocator {
+        return .{
+            .ptr = self,
+            .vtable = &vtable,
+        };
+    }
+
+    /// Deinitialize the allocator (no-op, the host owns the context).
+    pub fn deinit(self: *ExternalAllocator) void {
+        _ = self;
+    }
+
+    fn alloc(ctx: *anyopaque, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        _ = ret_addr;
+        const self: *ExternalAllocator = @ptrCast(@alignCast(ctx));
+        return self.alloc_fn(self.context, len, alignment.toByteUnits());
+    }
+
+    fn resize(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) bool {
+        _ = ctx;
+        _ = alignment;
+        _ = ret_addr;
+        return new_len == memory.len;
+    }
+
+    fn remap(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) ?[*]u8 {
+        _ = ret_addr;
+        const self: *ExternalAllocator = @ptrCast(@alignCast(ctx));
+        if (new_len == memory.len) {
+            return memory.ptr;
+        }
+        return self.realloc_fn(self.context, memory.ptr, memory.len, alignment.toByteUnits(), new_len);
+    }
+
+    fn free(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) void {
+        _ = ret_addr;
+        const self: *ExternalAllocator = @ptrCast(@alignCast(ctx));
+        self.free_fn(self.context, memory.ptr, memory.len, alignment.toByteUnits());
+    }
+};
+
+/// Create a new `ExternalAllocator` calling the given functions
+export fn zig_external_allocator_create(
+    config_ptr: ?*const ExternalAllocator.Config,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const config = (config_ptr orelse return .invalid_config).*;
+
+    const allocator = ffi.createWithConfig(ExternalAllocator, config) catch |err| return ffi.createStatus(err);
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
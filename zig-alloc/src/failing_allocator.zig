@@ -0,0 +1,219 @@
+//! Fault-injecting allocator for out-of-memory testing.
+//!
+//! This module provides an allocator modeled on `std.testing.FailingAllocator`
+//! that wraps the SMP allocator or any other `FfiAllocator`, and makes chosen
+//! allocations fail: all of them from a given one on, the ones past a byte budget, or random ones
+//! drawn from a seeded generator so that failures are reproducible.
+
+const std = @import("std");
+const ffi = @import("ffi.zig");
+
+/// Allocator failing on purpose, counting what goes through it.
+pub const FailingAllocator = struct {
+    /// Allocator serving the allocations that don't fail
+    backing: std.mem.Allocator,
+
+    /// Serializes access to the counters and the generator
+    mutex: std.Thread.Mutex = .{},
+
+    /// Number of allocations going through before all the next ones fail
+    fail_index: usize,
+
+    /// Total number of bytes that may be allocated
+    byte_limit: usize,
+
+    /// Probability for any allocation or growth to fail
+    failure_probability: f64,
+
+    /// Generator drawing the random failures
+    prng: std.Random.DefaultPrng,
+
+    /// Counters reported through the C ABI
+    stats: Stats = .{},
+
+    const vtable: std.mem.Allocator.VTable = .{
+        .alloc = alloc,
+        .resize = resize,
+        .remap = remap,
+        .free = free,
+    };
+
+    /// Configuration for creating failing allocators
+    pub const Config = extern struct {
+        /// `FfiAllocator` handle serving the allocations, the SMP allocator
+        /// if null. It must outlive the failing allocator.
+        backing_allocator: ?*anyopaque = null,
+
+        /// Index of the first allocation to fail, counting from 0. Like
+        /// `std.testing.FailingAllocator`, every allocation fails from then on.
+        fail_index: usize = std.math.maxInt(usize),
+
+        /// Total number of bytes that may be allocated, freed ones included
+        byte_limit: usize = std.math.maxInt(usize),
+
+        /// Probability for any allocation or growth to fail, from 0 to 1
+        failure_probability: f64 = 0,
+
+        /// Seed of the generator drawing the random failures
+        seed: u64 = 0,
+    };
+
+    /// Initialize a new failing allocator.
+    pub fn init(config: Config) ffi.CreateError!FailingAllocator {
+        if (!(config.failure_probability >= 0 and config.failure_probability <= 1)) {
+            return error.InvalidConfig;
+        }
+
+        const backing = if (ffi.opaquePtrToFfiAllocator(config.backing_allocator)) |parent|
+            parent.allocator
+        else
+            std.heap.smp_allocator;
+
+        return .{
+            .backing = backing,
+            .fail_index = config.fail_index,
+            .byte_limit = config.byte_limit,
+            .failure_probability = config.failure_probability,
+            .prng = .init(config.seed),
+        };
+    }
+
+    /// Get the Zig allocator interface.
+    pub fn allocator(self: *FailingAllocator) std.mem.Allocator {
+        return .{
+            .ptr = self,
+            .vtable = &vtable,
+        };
+    }
+
+    /// Counters of the allocations that went through so far.
+    pub fn getStats(self: *FailingAllocator) Stats {
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        return self.stats;
+    }
+
+    /// Deinitialize the allocator (no-op, the backing allocator owns the memory).
+    pub fn deinit(self: *FailingAllocator) void {
+        _ = self;
+    }
+
+    /// Whether requesting `extra` more bytes has to fail, counting the failure.
+    fn shouldFail(self: *FailingAllocator, extra: usize) bool {
+        const fail = extra > self.byte_limit -| self.stats.allocated_bytes or
+            (self.failure_probability > 0 and self.prng.random().float(f64) < self.failure_probability);
+
+        if (fail) {
+            self.stats.failures += 1;
+        }
+        return fail;
+    }
+
+    fn alloc(ctx: *anyopaque, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        const self: *FailingAllocator = @ptrCast(@alignCast(ctx));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        // Failed allocations don't count, so every allocation fails from the index on
+        if (self.stats.allocations == self.fail_index) {
+            self.stats.failures += 1;
+            return null;
+        }
+        if (self.shouldFail(len)) {
+            return null;
+        }
+
+        const ptr = self.backing.rawAlloc(len, alignment, ret_addr) orelse return null;
+        self.stats.allocations += 1;
+        self.stats.allocated_bytes += len;
+        return ptr;
+    }
+
+    fn resize(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) bool {
+        const self: *FailingAllocator = @ptrCast(@alignCast(ctx));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        if (new_len > memory.len and self.shouldFail(new_len - memory.len)) {
+            return false;
+        }
+
+        if (!self.backing.rawResize(memory, alignment, new_len, ret_addr)) {
+            return false;
+        }
+        self.countResize(memory.len, new_len);
+        return true;
+    }
+
+    fn remap(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) ?[*]u8 {
+        const self: *FailingAllocator = @ptrCast(@alignCast(ctx));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        if (new_len > memory.len and self.shouldFail(new_len - memory.len)) {
+            return null;
+        }
+
+        const new_ptr = self.backing.rawRemap(memory, alignment, new_len, ret_addr) orelse return null;
+        self.countResize(memory.len, new_len);
+        return new_ptr;
+    }
+
+    fn free(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) void {
+        const self: *FailingAllocator = @ptrCast(@alignCast(ctx));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        self.backing.rawFree(memory, alignment, ret_addr);
+        self.stats.deallocations += 1;
+        self.stats.freed_bytes += memory.len;
+    }
+
+    fn countResize(self: *FailingAllocator, old_len: usize, new_len: usize) void {
+        if (new_len > old_len) {
+            self.stats.allocated_bytes += new_len - old_len;
+        } else {
+            self.stats.freed_bytes += old_len - new_len;
+        }
+    }
+};
+
+/// Counters of a `FailingAllocator`, as reported through the C ABI
+pub const Stats = extern struct {
+    /// Successful allocations
+    allocations: usize = 0,
+
+    /// Frees
+    deallocations: usize = 0,
+
+    /// Bytes allocated, growths included
+    allocated_bytes: usize = 0,
+
+    /// Bytes freed, shrinks included
+    freed_bytes: usize = 0,
+
+    /// Allocations and growths failed on purpose
+    failures: usize = 0,
+};
+
+/// Create a new `FailingAllocator` with the given configuration
+export fn zig_failing_allocator_create(
+    config_ptr: ?*const FailingAllocator.Config,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const config = (config_ptr orelse return .invalid_config).*;
+
+    const allocator = ffi.createWithConfig(FailingAllocator, config) catch |err| return ffi.createStatus(err);
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
+
+/// Get the counters of a `FailingAllocator`
+export fn zig_failing_allocator_stats(allocator_ptr: ?*anyopaque, out_stats: *Stats) callconv(.c) bool {
+    const failing = ffi.opaquePtrToParent(FailingAllocator, allocator_ptr) orelse return false;
+    out_stats.* = failing.getStats();
+    return true;
+}
@@ -5,6 +5,30 @@
 
 const std = @import("std");
 
+/// Outcome of an exported `*_create` function.
+///
+/// The created allocator is written through an out-pointer, so that a
+/// failure can report why it happened instead of a bare null.
+pub const CreateStatus = enum(c_int) {
+    /// The allocator was created
+    ok = 0,
+    /// Not enough memory to set up the allocator
+    out_of_memory = 1,
+    /// The provided configuration is missing or unsupported
+    invalid_config = 2,
+};
+
+/// Errors that can occur while creating an allocator.
+pub const CreateError = std.mem.Allocator.Error || error{InvalidConfig};
+
+/// Maps a creation error onto the status reported through the C ABI.
+pub fn createStatus(err: CreateError) CreateStatus {
+    return switch (err) {
+        error.OutOfMemory => .out_of_memory,
+        error.InvalidConfig => .invalid_config,
+    };
+}
+
 /// FfiAllocator provides a C-compatible wrapper around Zig allocators.
 ///
 /// This struct manages the lifetime of the backing allocator and provides
@@ -127,18 +151,18 @@ pub const FfiAllocator = struct {
 /// 4. Returns a pointer to the heap-allocated FfiAllocator
 ///
 /// The type T must have:
-/// - An `init() T` function for initialization
+/// - An `init() T` (or `init() CreateError!T`) function for initialization
 /// - A `deinit(*T) void` method for cleanup
 /// - An `allocator(*T) std.mem.Allocator` method
 ///
-/// Returns an error if heap allocation fails.
+/// Returns an error if heap allocation or initialization fails.
 pub fn create(
     comptime T: type,
-) std.mem.Allocator.Error!*FfiAllocator {
+) CreateError!*FfiAllocator {
     const parent = try std.heap.c_allocator.create(T);
     errdefer std.heap.c_allocator.destroy(parent);
 
-    parent.* = T.init();
+    parent.* = try initParent(T, .{});
     errdefer parent.deinit();
 
     return wrapAllocated(T, parent);
@@ -148,22 +172,31 @@ pub fn create(
 ///
 /// The type T must have:
 /// - A `Config` declaration
-/// - An `init(T.Config) T` function for initialization
+/// - An `init(T.Config) T` (or `init(T.Config) CreateError!T`) function for initialization
 /// - A `deinit(*T) void` method for cleanup
 /// - An `allocator(*T) std.mem.Allocator` method
 pub fn createWithConfig(
     comptime T: type,
     config: T.Config,
-) std.mem.Allocator.Error!*FfiAllocator {
+) CreateError!*FfiAllocator {
     const parent = try std.heap.c_allocator.create(T);
     errdefer std.heap.c_allocator.destroy(parent);
 
-    parent.* = T.init(config);
+    parent.* = try initParent(T, .{config});
     errdefer parent.deinit();
 
     return wrapAllocated(T, parent);
 }
 
+/// Calls `T.init`, propagating its error if initialization can fail.
+inline fn initParent(comptime T: type, args: anytype) CreateError!T {
+    const result = @call(.auto, T.init, args);
+    if (@typeInfo(@TypeOf(result)) == .error_union) {
+        return try result;
+    }
+    return result;
+}
+
 fn wrapAllocated(
     comptime T: type,
     parent: *T,
@@ -185,6 +218,15 @@ pub inline fn opaquePtrToFfiAllocator(ptr: ?*anyopaque) ?*FfiAllocator {
     return @ptrCast(@alignCast(non_null_ptr));
 }
 
+/// Converts an opaque pointer to the parent allocator of a `*FfiAllocator`
+///
+/// Returns null if the provided pointer is null. The caller must guarantee
+/// that the `FfiAllocator` was created for a `T`.
+pub inline fn opaquePtrToParent(comptime T: type, ptr: ?*anyopaque) ?*T {
+    const allocator = opaquePtrToFfiAllocator(ptr) orelse return null;
+    return @ptrCast(@alignCast(allocator.parent));
+}
+
 // Generic handler for deinit
 fn DeinitHandler(comptime T: type) type {
     return struct {
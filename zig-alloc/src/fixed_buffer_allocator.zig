@@ -0,0 +1,82 @@
+//! Fixed buffer allocator over caller-provided memory.
+//!
+//! This module provides a thin wrapper around Zig's FixedBufferAllocator,
+//! which serves allocations from a single buffer owned by the caller and
+//! never touches the heap. Only the most recent allocation can be freed or
+//! resized in place, everything else is released by `reset`.
+
+const std = @import("std");
+const ffi = @import("ffi.zig");
+
+/// Wrapper for the standard fixed buffer allocator.
+///
+/// Not thread-safe, the buffer must outlive the allocator.
+pub const FixedBufferAllocator = struct {
+    /// The underlying fixed buffer allocator
+    fba: std.heap.FixedBufferAllocator,
+
+    /// Configuration for creating fixed buffer allocators
+    pub const Config = extern struct {
+        /// Start of the buffer allocations are served from
+        buffer: ?[*]u8 = null,
+
+        /// Length of `buffer` in bytes
+        len: usize = 0,
+    };
+
+    /// Initialize a new fixed buffer allocator over `config.buffer`.
+    pub fn init(config: Config) ffi.CreateError!FixedBufferAllocator {
+        const buffer = config.buffer orelse return error.InvalidConfig;
+
+        return .{
+            .fba = .init(buffer[0..config.len]),
+        };
+    }
+
+    /// Get the Zig allocator interface.
+    pub fn allocator(self: *FixedBufferAllocator) std.mem.Allocator {
+        return self.fba.allocator();
+    }
+
+    /// Free everything allocated so far.
+    pub fn reset(self: *FixedBufferAllocator) void {
+        self.fba.reset();
+    }
+
+    /// Number of bytes of the buffer used so far, alignment padding included.
+    pub fn endIndex(self: *FixedBufferAllocator) usize {
+        return self.fba.end_index;
+    }
+
+    /// Deinitialize the allocator (no-op, the buffer belongs to the caller).
+    pub fn deinit(self: *FixedBufferAllocator) void {
+        _ = self;
+    }
+};
+
+/// Create a new `FixedBufferAllocator` over a caller-provided buffer
+export fn zig_fixed_buffer_allocator_create(
+    config_ptr: ?*const FixedBufferAllocator.Config,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const config = (config_ptr orelse return .invalid_config).*;
+
+    const allocator = ffi.createWithConfig(FixedBufferAllocator, config) catch |err| return ffi.createStatus(err);
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
+
+/// Free everything allocated from a `FixedBufferAllocator`
+export fn zig_fixed_buffer_allocator_reset(allocator_ptr: ?*anyopaque) callconv(.c) bool {
+    const fba = ffi.opaquePtrToParent(FixedBufferAllocator, allocator_ptr) orelse return false;
+    fba.reset();
+    return true;
+}
+
+/// Number of bytes of the buffer of a `FixedBufferAllocator` used so far
+export fn zig_fixed_buffer_allocator_end_index(allocator_ptr: ?*anyopaque) callconv(.c) usize {
+    const fba = ffi.opaquePtrToParent(FixedBufferAllocator, allocator_ptr) orelse return 0;
+    return fba.endIndex();
+}
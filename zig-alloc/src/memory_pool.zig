@@ -0,0 +1,197 @@
+//! Pool of same-sized items.
+//!
+//! This module provides the runtime-sized counterpart of Zig's
+//! `std.heap.MemoryPool`, since the item type is only known on the foreign
+//! side. Items are carved out of an arena backed by the SMP allocator, or any
+//! other `FfiAllocator`, and freed items go on a free list, so both creation and destruction are O(1).
+
+const std = @import("std");
+const ffi = @import("ffi.zig");
+
+/// Pool handing out items of a single size and alignment.
+///
+/// Exposed through the `std.mem.Allocator` interface: allocations that don't
+/// fit in an item fail, freeing an item puts it back on the free list.
+pub const MemoryPool = struct {
+    /// Memory the items are carved out of
+    arena: std.heap.ArenaAllocator,
+
+    /// Freed items, ready to be handed out again
+    free_list: ?*Node = null,
+
+    /// Size of an item, large enough to hold a `Node`
+    item_size: usize,
+
+    /// Alignment of an item, at least the one of a `Node`
+    item_alignment: std.mem.Alignment,
+
+    /// Whether new items may be allocated once the preheated ones are in use
+    growable: bool,
+
+    /// Number of items allocated up front and after every reset
+    preheat: usize,
+
+    /// Free item, overlapping its memory
+    const Node = struct {
+        next: ?*Node,
+    };
+
+    const vtable: std.mem.Allocator.VTable = .{
+        .alloc = alloc,
+        .resize = resize,
+        .remap = std.mem.Allocator.noRemap,
+        .free = free,
+    };
+
+    /// Configuration for creating memory pools
+    pub const Config = extern struct {
+        /// `FfiAllocator` handle the items are carved out of, the SMP
+        /// allocator if null. It must outlive the pool.
+        backing_allocator: ?*anyopaque = null,
+
+        /// Size of an item in bytes
+        item_size: usize = 0,
+
+        /// Alignment of an item in bytes, a power of two
+        item_alignment: usize = 1,
+
+        /// Number of items allocated up front
+        preheat: usize = 0,
+
+        /// Whether the pool may grow past the preheated items
+        growable: bool = true,
+    };
+
+    /// Initialize a new memory pool, allocating the preheated items.
+    pub fn init(config: Config) ffi.CreateError!MemoryPool {
+        if (!std.math.isPowerOfTwo(config.item_alignment)) {
+            return error.InvalidConfig;
+        }
+
+        const item_alignment = std.mem.Alignment.fromByteUnits(@max(config.item_alignment, @alignOf(Node)));
+        const item_size = item_alignment.forward(@max(config.item_size, @sizeOf(Node)));
+
+        const backing = if (ffi.opaquePtrToFfiAllocator(config.backing_allocator)) |parent|
+            parent.allocator
+        else
+            std.heap.smp_allocator;
+
+        var pool: MemoryPool = .{
+            .arena = std.heap.ArenaAllocator.init(backing),
+            .item_size = item_size,
+            .item_alignment = item_alignment,
+            .growable = config.growable,
+            .preheat = config.preheat,
+        };
+        errdefer pool.arena.deinit();
+
+        try pool.preheatItems();
+        return pool;
+    }
+
+    /// Get the Zig allocator interface.
+    pub fn allocator(self: *MemoryPool) std.mem.Allocator {
+        return .{
+            .ptr = self,
+            .vtable = &vtable,
+        };
+    }
+
+    /// Hand out an item, or null if the pool is exhausted.
+    pub fn create(self: *MemoryPool) ?[*]u8 {
+        if (self.free_list) |node| {
+            self.free_list = node.next;
+            return @ptrCast(node);
+        }
+
+        if (!self.growable) {
+            return null;
+        }
+
+        return self.allocateItem();
+    }
+
+    /// Put an item back on the free list.
+    pub fn destroy(self: *MemoryPool, item: [*]u8) void {
+        const node: *Node = @ptrCast(@alignCast(item));
+        node.* = .{ .next = self.free_list };
+        self.free_list = node;
+    }
+
+    /// Free every item at once, keeping the memory for the next ones.
+    ///
+    /// Returns false if the preheated items could not be allocated again.
+    pub fn reset(self: *MemoryPool) bool {
+        self.free_list = null;
+        _ = self.arena.reset(.retain_capacity);
+
+        self.preheatItems() catch return false;
+        return true;
+    }
+
+    /// Deinitialize the pool, freeing all items at once.
+    pub fn deinit(self: *MemoryPool) void {
+        self.arena.deinit();
+    }
+
+    fn preheatItems(self: *MemoryPool) std.mem.Allocator.Error!void {
+        for (0..self.preheat) |_| {
+            const item = self.allocateItem() orelse return error.OutOfMemory;
+            self.destroy(item);
+        }
+    }
+
+    fn allocateItem(self: *MemoryPool) ?[*]u8 {
+        return self.arena.allocator().rawAlloc(self.item_size, self.item_alignment, @returnAddress());
+    }
+
+    fn fits(self: *MemoryPool, len: usize, alignment: std.mem.Alignment) bool {
+        return len <= self.item_size and alignment.compare(.lte, self.item_alignment);
+    }
+
+    fn alloc(ctx: *anyopaque, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        _ = ret_addr;
+        const self: *MemoryPool = @ptrCast(@alignCast(ctx));
+
+        if (!self.fits(len, alignment)) {
+            return null;
+        }
+        return self.create();
+    }
+
+    fn resize(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) bool {
+        _ = memory;
+        _ = ret_addr;
+        const self: *MemoryPool = @ptrCast(@alignCast(ctx));
+
+        return self.fits(new_len, alignment);
+    }
+
+    fn free(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) void {
+        _ = alignment;
+        _ = ret_addr;
+        const self: *MemoryPool = @ptrCast(@alignCast(ctx));
+
+        self.destroy(memory.ptr);
+    }
+};
+
+/// Create a new `MemoryPool` with the given configuration
+export fn zig_memory_pool_create(
+    config_ptr: ?*const MemoryPool.Config,
+    out_allocator: *?*anyopaque,
+) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const config = (config_ptr orelse return .invalid_config).*;
+
+    const allocator = ffi.createWithConfig(MemoryPool, config) catch |err| return ffi.createStatus(err);
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
+
+/// Free every item of a `MemoryPool` at once
+export fn zig_memory_pool_reset(allocator_ptr: ?*anyopaque) callconv(.c) bool {
+    const pool = ffi.opaquePtrToParent(MemoryPool, allocator_ptr) orelse return false;
+    return pool.reset();
+}
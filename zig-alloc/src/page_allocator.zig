@@ -0,0 +1,39 @@
+//! Page allocator wrapper.
+//!
+//! This module provides a thin wrapper around Zig's page allocator, which maps
+//! every allocation straight from the OS. It makes a plain base for other
+//! allocators to request their pages from.
+
+const std = @import("std");
+const ffi = @import("ffi.zig");
+
+/// Wrapper for the standard page allocator.
+///
+/// This is a zero-cost abstraction that provides the necessary
+/// interface for FFI compatibility.
+pub const PageAllocator = struct {
+    /// Initialize a new page allocator instance.
+    pub fn init() PageAllocator {
+        return .{};
+    }
+
+    /// Get the Zig allocator interface.
+    pub fn allocator(self: *PageAllocator) std.mem.Allocator {
+        _ = self;
+        return std.heap.page_allocator;
+    }
+
+    /// Deinitialize the allocator (no-op for the page allocator).
+    pub fn deinit(self: *PageAllocator) void {
+        _ = self;
+    }
+};
+
+/// Create a new `PageAllocator`
+export fn zig_page_allocator_create(out_allocator: *?*anyopaque) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const allocator = ffi.create(PageAllocator) catch return .out_of_memory;
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
@@ -9,12 +9,33 @@ const std = @import("std");
 /// Arena allocator backed by the SMP allocator
 pub const arena_smp = @import("arena_smp_allocator.zig");
 
+/// Allocators over libc `malloc`
+pub const c = @import("c_allocator.zig");
+
 /// Debug allocator with leak detection and safety features
 pub const debug = @import("debug_allocator.zig");
 
+/// Allocator implemented by the host language
+pub const external = @import("external_allocator.zig");
+
+/// Fault-injecting allocator for out-of-memory testing
+pub const failing = @import("failing_allocator.zig");
+
+/// Allocator over a caller-provided buffer
+pub const fixed_buffer = @import("fixed_buffer_allocator.zig");
+
+/// Allocator mapping every allocation from the OS
+pub const page = @import("page_allocator.zig");
+
+/// Pool of same-sized items
+pub const memory_pool = @import("memory_pool.zig");
+
 /// Thread-safe general-purpose allocator
 pub const smp = @import("smp_allocator.zig");
 
+/// Independent SMP-style heaps owning their memory
+pub const smp_heap = @import("smp_heap.zig");
+
 /// FFI utilities for C-compatible allocator interface
 pub const ffi = @import("ffi.zig");
 
@@ -25,8 +46,15 @@ pub const ffi_exports = @import("ffi_exports.zig");
 comptime {
     // Reference modules to ensure their export functions are included
     _ = arena_smp;
+    _ = c;
     _ = debug;
+    _ = external;
+    _ = failing;
+    _ = fixed_buffer;
+    _ = memory_pool;
+    _ = page;
     _ = smp;
+    _ = smp_heap;
     _ = ffi_exports;
 }
 
@@ -30,7 +30,10 @@ pub const SmpAllocator = struct {
 };
 
 /// Create a new `SmpAllocator`
-export fn zig_smp_allocator_create() callconv(.c) ?*anyopaque {
-    const allocator = ffi.create(SmpAllocator) catch return null;
-    return @ptrCast(allocator);
+export fn zig_smp_allocator_create(out_allocator: *?*anyopaque) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const allocator = ffi.create(SmpAllocator) catch return .out_of_memory;
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
 }
@@ -0,0 +1,462 @@
+//! Isolated SMP-style heap.
+//!
+//! `std.heap.smp_allocator` is a process-wide singleton: every user shares its
+//! slabs, and they are never given back to the OS. This module provides the
+//! same design, per-thread caches handing out size-classed slots from slabs,
+//! as a value owning its memory. Each instance is an independent heap that
+//! unmaps all its slabs and large allocations when deinitialized, and `trim`
+//! gives the memory of free slots back to the OS on demand.
+
+const std = @import("std");
+const builtin = @import("builtin");
+const ffi = @import("ffi.zig");
+
+/// Maximum number of per-thread caches of a heap
+const max_thread_count = 128;
+
+/// Size of a slab, slots of one size class are carved out of it
+const slab_len: usize = @max(std.heap.page_size_max, 64 * 1024);
+
+/// Smallest size class, large enough to hold a free list node
+const min_class = std.math.log2(@sizeOf(usize));
+
+/// Number of size classes served from slabs, larger allocations get their own mapping
+const size_class_count = std.math.log2(slab_len) - min_class;
+
+/// Number of other caches searched for a free slot before mapping a new slab
+const max_alloc_search = 1;
+
+/// Index of the cache the current thread locked last, a hint shared by every heap
+threadlocal var thread_index: u32 = 0;
+
+/// Whether the pages of free slots can be discarded without unmapping them
+const can_discard = builtin.os.tag != .windows and builtin.os.tag != .wasi;
+
+/// Set in the free list link of a slot whose pages were discarded since it was freed
+const discarded_mark: usize = 1;
+
+/// Heap with its own slabs, modeled on `std.heap.SmpAllocator`.
+pub const SmpHeap = struct {
+    /// Per-thread caches, threads move to another one when theirs is busy
+    caches: [max_thread_count]Cache = @splat(.{}),
+
+    /// Number of caches in use
+    cpu_count: u32,
+
+    /// Protects `slabs` and `large`
+    mutex: std.Thread.Mutex = .{},
+
+    /// Addresses of every slab mapped so far
+    slabs: std.AutoHashMapUnmanaged(usize, void) = .empty,
+
+    /// Allocations too large for a slab, keyed by address
+    large: std.AutoHashMapUnmanaged(usize, Mapping) = .empty,
+
+    /// Slots of one thread, one slab and free list per size class
+    const Cache = struct {
+        /// Protects the slots
+        mutex: std.Thread.Mutex = .{},
+
+        /// Next unused slot of the current slab of each class, a multiple
+        /// of `slab_len` once the slab is used up
+        next_addrs: [size_class_count]usize = @splat(0),
+
+        /// Head of the free list of each class, 0 if empty
+        frees: [size_class_count]usize = @splat(0),
+    };
+
+    /// Allocation mapped on its own
+    const Mapping = struct {
+        len: usize,
+        alignment: std.mem.Alignment,
+    };
+
+    const vtable: std.mem.Allocator.VTable = .{
+        .alloc = alloc,
+        .resize = resize,
+        .remap = remap,
+        .free = free,
+    };
+
+    /// Initialize a new heap, mapping nothing until the first allocation.
+    pub fn init() SmpHeap {
+        const cpu_count = std.Thread.getCpuCount() catch max_thread_count;
+        return .{ .cpu_count = @intCast(std.math.clamp(cpu_count, 1, max_thread_count)) };
+    }
+
+    /// Get the Zig allocator interface.
+    pub fn allocator(self: *SmpHeap) std.mem.Allocator {
+        return .{
+            .ptr = self,
+            .vtable = &vtable,
+        };
+    }
+
+    /// Deinitialize the heap, unmapping all its memory, allocations still in use included.
+    pub fn deinit(self: *SmpHeap) void {
+        var slabs = self.slabs.keyIterator();
+        while (slabs.next()) |address| {
+            const slab: [*]u8 = @ptrFromInt(address.*);
+            std.heap.page_allocator.rawFree(slab[0..slab_len], .fromByteUnits(slab_len), @returnAddress());
+        }
+        self.slabs.deinit(std.heap.c_allocator);
+
+        var large = self.large.iterator();
+        while (large.next()) |entry| {
+            const memory: [*]u8 = @ptrFromInt(entry.key_ptr.*);
+            std.heap.page_allocator.rawFree(memory[0..entry.value_ptr.len], entry.value_ptr.alignment, @returnAddress());
+        }
+        self.large.deinit(std.heap.c_allocator);
+    }
+
+    /// Number of bytes mapped from the OS, slabs and large allocations.
+    pub fn mappedBytes(self: *SmpHeap) usize {
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        var total = self.slabs.count() * slab_len;
+        var large = self.large.valueIterator();
+        while (large.next()) |mapping| {
+            total += mapping.len;
+        }
+        return total;
+    }
+
+    /// Give the memory of free slots back to the OS, returning the number of bytes released.
+    ///
+    /// Slabs with every slot free are unmapped. Elsewhere, the pages of free
+    /// slots spanning more than a page are discarded with `madvise(MADV_DONTNEED)`,
+    /// and only count once until the slot is reused. Allocations wait until
+    /// trimming is done.
+    pub fn trim(self: *SmpHeap) usize {
+        const caches = self.caches[0..self.cpu_count];
+        for (caches) |*cache| {
+            cache.mutex.lock();
+        }
+        defer {
+            for (caches) |*cache| {
+                cache.mutex.unlock();
+            }
+        }
+
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        // Without room to count free bytes, no slab is known to be empty
+        var usage: std.AutoHashMapUnmanaged(usize, SlabUsage) = .empty;
+        defer usage.deinit(std.heap.c_allocator);
+        countFreeBytes(caches, &usage) catch usage.clearRetainingCapacity();
+
+        var released: usize = 0;
+        for (caches) |*cache| {
+            for (0..size_class_count) |class| {
+                // Rebuild the free list without the slots of the slabs about to be unmapped
+                var kept: usize = 0;
+                var address = cache.frees[class];
+                while (address != 0) {
+                    const node: *usize = @ptrFromInt(address);
+                    const link = node.*;
+                    address = link & ~discarded_mark;
+
+                    if (isEmptySlab(&usage, @intFromPtr(node))) continue;
+
+                    // Pages discarded by an earlier call were counted then, and left untouched since
+                    var mark = link & discarded_mark;
+                    if (mark == 0) {
+                        const len = discardSlot(@intFromPtr(node), slotSize(class));
+                        if (len != 0) mark = discarded_mark;
+                        released += len;
+                    }
+                    node.* = kept | mark;
+                    kept = @intFromPtr(node);
+                }
+                cache.frees[class] = kept;
+
+                if (cache.next_addrs[class] % slab_len != 0 and isEmptySlab(&usage, cache.next_addrs[class])) {
+                    cache.next_addrs[class] = 0;
+                }
+            }
+        }
+
+        var slabs = usage.iterator();
+        while (slabs.next()) |entry| {
+            if (entry.value_ptr.free != slab_len) continue;
+
+            _ = self.slabs.remove(entry.key_ptr.*);
+            const slab: [*]u8 = @ptrFromInt(entry.key_ptr.*);
+            std.heap.page_allocator.rawFree(slab[0..slab_len], .fromByteUnits(slab_len), @returnAddress());
+            released += slab_len - entry.value_ptr.discarded;
+        }
+
+        return released;
+    }
+
+    /// Lock the cache of the current thread, or the next one available.
+    fn lockCache(self: *SmpHeap) *Cache {
+        var index = thread_index % self.cpu_count;
+        {
+            const cache = &self.caches[index];
+            if (cache.mutex.tryLock()) {
+                @branchHint(.likely);
+                return cache;
+            }
+        }
+
+        while (true) {
+            index = (index + 1) % self.cpu_count;
+            const cache = &self.caches[index];
+            if (cache.mutex.tryLock()) {
+                thread_index = index;
+                return cache;
+            }
+        }
+    }
+
+    /// Map a new slab and record it.
+    fn mapSlab(self: *SmpHeap) ?[*]u8 {
+        // Aligning slabs to their length lets `next_addrs` tell when one is used up
+        const slab = std.heap.page_allocator.rawAlloc(slab_len, .fromByteUnits(slab_len), @returnAddress()) orelse return null;
+
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        self.slabs.put(std.heap.c_allocator, @intFromPtr(slab), {}) catch {
+            std.heap.page_allocator.rawFree(slab[0..slab_len], .fromByteUnits(slab_len), @returnAddress());
+            return null;
+        };
+        return slab;
+    }
+
+    /// Map an allocation too large for a slab and record it.
+    fn mapLarge(self: *SmpHeap, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        const memory = std.heap.page_allocator.rawAlloc(len, alignment, ret_addr) orelse return null;
+
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        self.large.put(std.heap.c_allocator, @intFromPtr(memory), .{ .len = len, .alignment = alignment }) catch {
+            std.heap.page_allocator.rawFree(memory[0..len], alignment, ret_addr);
+            return null;
+        };
+        return memory;
+    }
+
+    fn alloc(ctx: *anyopaque, len: usize, alignment: std.mem.Alignment, ret_addr: usize) ?[*]u8 {
+        const self: *SmpHeap = @ptrCast(@alignCast(ctx));
+
+        const class = sizeClassIndex(len, alignment);
+        if (class >= size_class_count) {
+            @branchHint(.unlikely);
+            return self.mapLarge(len, alignment, ret_addr);
+        }
+
+        const slot_size = slotSize(class);
+        var search_count: u8 = 0;
+        var cache = self.lockCache();
+
+        outer: while (true) {
+            const top_free_ptr = cache.frees[class];
+            if (top_free_ptr != 0) {
+                @branchHint(.likely);
+                defer cache.mutex.unlock();
+                const node: *usize = @ptrFromInt(top_free_ptr);
+                cache.frees[class] = node.* & ~discarded_mark;
+                return @ptrFromInt(top_free_ptr);
+            }
+
+            const next_addr = cache.next_addrs[class];
+            if (next_addr % slab_len != 0) {
+                @branchHint(.likely);
+                defer cache.mutex.unlock();
+                cache.next_addrs[class] = next_addr + slot_size;
+                return @ptrFromInt(next_addr);
+            }
+
+            if (search_count >= max_alloc_search) {
+                defer cache.mutex.unlock();
+                const slab = self.mapSlab() orelse return null;
+                cache.next_addrs[class] = @intFromPtr(slab) + slot_size;
+                return slab;
+            }
+
+            // Look for free slots in the cache of another thread before mapping more memory
+            cache.mutex.unlock();
+            var index = thread_index % self.cpu_count;
+            while (true) {
+                index = (index + 1) % self.cpu_count;
+                cache = &self.caches[index];
+                if (cache.mutex.tryLock()) {
+                    thread_index = index;
+                    search_count += 1;
+                    continue :outer;
+                }
+            }
+        }
+    }
+
+    fn resize(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) bool {
+        const self: *SmpHeap = @ptrCast(@alignCast(ctx));
+
+        const class = sizeClassIndex(memory.len, alignment);
+        const new_class = sizeClassIndex(new_len, alignment);
+        if (class < size_class_count) {
+            return new_class == class;
+        }
+        if (new_class < size_class_count) {
+            return false;
+        }
+
+        if (!std.heap.page_allocator.rawResize(memory, alignment, new_len, ret_addr)) {
+            return false;
+        }
+        self.moveLarge(memory.ptr, memory.ptr, new_len);
+        return true;
+    }
+
+    fn remap(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, new_len: usize, ret_addr: usize) ?[*]u8 {
+        const self: *SmpHeap = @ptrCast(@alignCast(ctx));
+
+        const class = sizeClassIndex(memory.len, alignment);
+        const new_class = sizeClassIndex(new_len, alignment);
+        if (class < size_class_count) {
+            return if (new_class == class) memory.ptr else null;
+        }
+        if (new_class < size_class_count) {
+            return null;
+        }
+
+        const new_memory = std.heap.page_allocator.rawRemap(memory, alignment, new_len, ret_addr) orelse return null;
+        self.moveLarge(memory.ptr, new_memory, new_len);
+        return new_memory;
+    }
+
+    fn free(ctx: *anyopaque, memory: []u8, alignment: std.mem.Alignment, ret_addr: usize) void {
+        const self: *SmpHeap = @ptrCast(@alignCast(ctx));
+
+        const class = sizeClassIndex(memory.len, alignment);
+        if (class >= size_class_count) {
+            @branchHint(.unlikely);
+            {
+                self.mutex.lock();
+                defer self.mutex.unlock();
+                _ = self.large.remove(@intFromPtr(memory.ptr));
+            }
+            std.heap.page_allocator.rawFree(memory, alignment, ret_addr);
+            return;
+        }
+
+        const node: *usize = @ptrCast(@alignCast(memory.ptr));
+        const cache = self.lockCache();
+        defer cache.mutex.unlock();
+        node.* = cache.frees[class];
+        cache.frees[class] = @intFromPtr(node);
+    }
+
+    /// Update the record of a large allocation resized or moved by the page allocator.
+    fn moveLarge(self: *SmpHeap, old_ptr: [*]u8, new_ptr: [*]u8, new_len: usize) void {
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        var mapping = self.large.fetchRemove(@intFromPtr(old_ptr)).?.value;
+        mapping.len = new_len;
+
+        // The removal left room for the new entry
+        self.large.putAssumeCapacityNoClobber(@intFromPtr(new_ptr), mapping);
+    }
+};
+
+/// Free bytes of a slab, as counted by `trim`
+const SlabUsage = struct {
+    /// Bytes of free slots and of the unused tail
+    free: usize = 0,
+
+    /// Bytes of free slots already given back by an earlier `trim`
+    discarded: usize = 0,
+};
+
+/// Sum up the free bytes of every slab, free slots and unused tails alike
+fn countFreeBytes(caches: []SmpHeap.Cache, usage: *std.AutoHashMapUnmanaged(usize, SlabUsage)) std.mem.Allocator.Error!void {
+    for (caches) |*cache| {
+        for (0..size_class_count) |class| {
+            var address = cache.frees[class];
+            while (address != 0) {
+                const link = @as(*usize, @ptrFromInt(address)).*;
+                const discarded = if (link & discarded_mark != 0) discardableLen(address, slotSize(class)) else 0;
+                try addFreeBytes(usage, address, slotSize(class), discarded);
+                address = link & ~discarded_mark;
+            }
+
+            const next_addr = cache.next_addrs[class];
+            if (next_addr % slab_len != 0) {
+                try addFreeBytes(usage, next_addr, slab_len - next_addr % slab_len, 0);
+            }
+        }
+    }
+}
+
+fn addFreeBytes(usage: *std.AutoHashMapUnmanaged(usize, SlabUsage), address: usize, len: usize, discarded: usize) std.mem.Allocator.Error!void {
+    const entry = try usage.getOrPut(std.heap.c_allocator, std.mem.alignBackward(usize, address, slab_len));
+    if (!entry.found_existing) {
+        entry.value_ptr.* = .{};
+    }
+    entry.value_ptr.free += len;
+    entry.value_ptr.discarded += discarded;
+}
+
+/// Whether every slot of the slab holding `address` is free
+fn isEmptySlab(usage: *const std.AutoHashMapUnmanaged(usize, SlabUsage), address: usize) bool {
+    const slab = usage.get(std.mem.alignBackward(usize, address, slab_len)) orelse return false;
+    return slab.free == slab_len;
+}
+
+/// Start of the pages of a free slot that can be discarded, past the one holding its free list node
+fn discardStart(address: usize) usize {
+    return std.mem.alignForward(usize, address + @sizeOf(usize), std.heap.pageSize());
+}
+
+/// Number of bytes of a free slot that can be discarded
+fn discardableLen(address: usize, slot_size: usize) usize {
+    if (!can_discard) return 0;
+    return (address + slot_size) -| discardStart(address);
+}
+
+/// Discard the pages of a free slot, returning the number of bytes released
+fn discardSlot(address: usize, slot_size: usize) usize {
+    const len = discardableLen(address, slot_size);
+    if (len == 0) return 0;
+
+    const pages: [*]align(std.heap.page_size_min) u8 = @ptrFromInt(discardStart(address));
+    std.posix.madvise(pages, len, std.posix.MADV.DONTNEED) catch return 0;
+    return len;
+}
+
+/// Size class of an allocation, classes past `size_class_count` don't fit in a slab
+fn sizeClassIndex(len: usize, alignment: std.mem.Alignment) usize {
+    return @max(@bitSizeOf(usize) - @clz(len -| 1), @intFromEnum(alignment), min_class) - min_class;
+}
+
+/// Size of the slots of a class
+fn slotSize(class: usize) usize {
+    return @as(usize, 1) << @intCast(class + min_class);
+}
+
+/// Create a new `SmpHeap`
+export fn zig_smp_heap_create(out_allocator: *?*anyopaque) callconv(.c) ffi.CreateStatus {
+    out_allocator.* = null;
+
+    const allocator = ffi.create(SmpHeap) catch return .out_of_memory;
+    out_allocator.* = @ptrCast(allocator);
+    return .ok;
+}
+
+/// Give the memory of the free slots of a `SmpHeap` back to the OS
+export fn zig_smp_heap_trim(allocator_ptr: ?*anyopaque) callconv(.c) usize {
+    const heap = ffi.opaquePtrToParent(SmpHeap, allocator_ptr) orelse return 0;
+    return heap.trim();
+}
+
+/// Number of bytes a `SmpHeap` mapped from the OS
+export fn zig_smp_heap_mapped_bytes(allocator_ptr: ?*anyopaque) callconv(.c) usize {
+    const heap = ffi.opaquePtrToParent(SmpHeap, allocator_ptr) orelse return 0;
+    return heap.mappedBytes();
+}
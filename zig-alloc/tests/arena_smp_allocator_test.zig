@@ -4,7 +4,7 @@ const zig_alloc = @import("zig-alloc");
 const arena_smp_allocator = zig_alloc.arena_smp;
 
 test "ArenaSmpAllocator basic functionality" {
-    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init();
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -27,7 +27,7 @@ test "ArenaSmpAllocator basic functionality" {
 }
 
 test "ArenaSmpAllocator multiple allocations" {
-    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init();
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -57,7 +57,7 @@ test "ArenaSmpAllocator multiple allocations" {
 }
 
 test "ArenaSmpAllocator many small allocations" {
-    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init();
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -80,7 +80,7 @@ test "ArenaSmpAllocator many small allocations" {
 }
 
 test "ArenaSmpAllocator alignment" {
-    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init();
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -97,7 +97,7 @@ test "ArenaSmpAllocator alignment" {
 }
 
 test "ArenaSmpAllocator bulk pattern" {
-    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init();
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -122,4 +122,225 @@ test "ArenaSmpAllocator bulk pattern" {
     }
     
     // Everything gets freed at once when arena is deinitialized
-}
\ No newline at end of file
+}
+test "ArenaSmpAllocator reset retains capacity" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    _ = try allocator.alloc(u8, 4096);
+    const capacity = allocator_instance.arena.queryCapacity();
+
+    try testing.expect(allocator_instance.reset(.retain_capacity));
+    try testing.expectEqual(capacity, allocator_instance.arena.queryCapacity());
+
+    // Fits in the retained buffer
+    _ = try allocator.alloc(u8, 1024);
+    try testing.expectEqual(capacity, allocator_instance.arena.queryCapacity());
+
+    try testing.expect(allocator_instance.reset(.{ .retain_with_limit = 512 }));
+    try testing.expect(allocator_instance.arena.queryCapacity() <= 512);
+
+    try testing.expect(allocator_instance.reset(.free_all));
+    try testing.expectEqual(@as(usize, 0), allocator_instance.arena.queryCapacity());
+}
+
+test "ArenaSmpAllocator rollback within a buffer" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    _ = try allocator.alloc(u8, 64);
+    const saved = try allocator_instance.checkpoint();
+
+    const speculative = try allocator.alloc(u8, 64);
+    try testing.expect(allocator_instance.rollback(saved));
+
+    // The rolled back memory is handed out again
+    const reused = try allocator.alloc(u8, 64);
+    try testing.expectEqual(speculative.ptr, reused.ptr);
+}
+
+test "ArenaSmpAllocator rollback frees newer buffers" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    _ = try allocator.alloc(u8, 16);
+    const saved = try allocator_instance.checkpoint();
+    const capacity = allocator_instance.arena.queryCapacity();
+
+    // Large enough to need new buffers
+    _ = try allocator.alloc(u8, 64 * 1024);
+    _ = try allocator.alloc(u8, 256 * 1024);
+    try testing.expect(allocator_instance.arena.queryCapacity() > capacity);
+
+    try testing.expect(allocator_instance.rollback(saved));
+    try testing.expectEqual(capacity, allocator_instance.arena.queryCapacity());
+}
+
+test "ArenaSmpAllocator rejects stale checkpoints" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const empty = try allocator_instance.checkpoint();
+    _ = try allocator.alloc(u8, 16);
+    const later = try allocator_instance.checkpoint();
+
+    // Rolling back past `later` invalidates it
+    try testing.expect(allocator_instance.rollback(empty));
+    try testing.expect(!allocator_instance.canRollback(later));
+
+    _ = try allocator.alloc(u8, 16);
+    const before_reset = try allocator_instance.checkpoint();
+    _ = allocator_instance.reset(.retain_capacity);
+    try testing.expect(!allocator_instance.rollback(before_reset));
+}
+
+test "ArenaSmpAllocator rejects checkpoints rolled past and reached again" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const outer = try allocator_instance.checkpoint();
+    _ = try allocator.alloc(u8, 16);
+    const inner = try allocator_instance.checkpoint();
+    try testing.expect(allocator_instance.rollback(outer));
+
+    // Back past the position of `inner`, which stays stale
+    _ = try allocator.alloc(u8, 256);
+    try testing.expect(!allocator_instance.rollback(inner));
+
+    // `outer` can still be rolled back to, again and again
+    try testing.expect(allocator_instance.rollback(outer));
+    try testing.expect(allocator_instance.rollback(outer));
+}
+
+test "ArenaSmpAllocator rejects checkpoints of another arena" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+    var other = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer other.deinit();
+
+    _ = try allocator_instance.allocator().alloc(u8, 16);
+
+    // Taken while the other arena had no buffer yet
+    const foreign = try other.checkpoint();
+    try testing.expect(!allocator_instance.canRollback(foreign));
+    try testing.expect(!allocator_instance.rollback(foreign));
+}
+
+test "ArenaSmpAllocator stats" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const empty = allocator_instance.stats();
+    try testing.expectEqual(@as(usize, 0), empty.capacity);
+    try testing.expectEqual(@as(usize, 0), empty.chunks);
+
+    _ = try allocator.alloc(u8, 100);
+    const freed = try allocator.alloc(u8, 50);
+    allocator.free(freed);
+
+    const stats = allocator_instance.stats();
+    try testing.expectEqual(@as(usize, 100), stats.used);
+    try testing.expectEqual(@as(usize, 1), stats.chunks);
+    try testing.expect(stats.capacity >= 100);
+
+    // Doesn't fit in the first buffer, its tail is wasted
+    _ = try allocator.alloc(u8, 2 * stats.capacity);
+    const grown = allocator_instance.stats();
+    try testing.expectEqual(@as(usize, 2), grown.chunks);
+    try testing.expectEqual(100 + 2 * stats.capacity, grown.used);
+    try testing.expect(grown.wasted > 0);
+
+    _ = allocator_instance.reset(.retain_capacity);
+    try testing.expectEqual(@as(usize, 0), allocator_instance.stats().used);
+}
+
+test "ArenaSmpAllocator rollback keeps resizes of older allocations" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const shrunk = try allocator.alloc(u8, 100);
+    const freed = try allocator.alloc(u8, 50);
+    const outer = try allocator_instance.checkpoint();
+    _ = try allocator.alloc(u8, 30);
+    const inner = try allocator_instance.checkpoint();
+    _ = try allocator.alloc(u8, 20);
+
+    try testing.expect(allocator.resize(shrunk, 40));
+    allocator.free(freed);
+    try testing.expectEqual(@as(usize, 90), allocator_instance.stats().used);
+
+    try testing.expect(allocator_instance.rollback(inner));
+    try testing.expectEqual(@as(usize, 70), allocator_instance.stats().used);
+
+    try testing.expect(allocator_instance.rollback(outer));
+    try testing.expectEqual(@as(usize, 40), allocator_instance.stats().used);
+}
+
+test "ArenaSmpAllocator over another FfiAllocator" {
+    const debug = try zig_alloc.ffi.createWithConfig(zig_alloc.debug.DebugAllocator, .{});
+    defer debug.destroy();
+    const debug_allocator = zig_alloc.ffi.opaquePtrToParent(zig_alloc.debug.DebugAllocator, debug).?;
+
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{ .backing_allocator = debug });
+    const allocator = allocator_instance.allocator();
+
+    _ = try allocator.alloc(u8, 100);
+    const capacity = allocator_instance.stats().capacity;
+
+    // The arena buffers are tracked by the debug allocator
+    try testing.expectEqual(capacity, debug_allocator.totalRequestedBytes());
+
+    allocator_instance.deinit();
+    try testing.expectEqual(@as(usize, 0), debug_allocator.totalRequestedBytes());
+}
+
+fn allocateConcurrently(allocator: std.mem.Allocator, seed: u8) !void {
+    for (0..1000) |i| {
+        const bytes = try allocator.alloc(u8, 1 + i % 64);
+        @memset(bytes, seed);
+        for (bytes) |byte| {
+            try testing.expectEqual(seed, byte);
+        }
+    }
+}
+
+test "ArenaSmpAllocator thread safe" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{});
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    var threads: [4]std.Thread = undefined;
+    for (&threads, 0..) |*thread, i| {
+        thread.* = try std.Thread.spawn(.{}, allocateConcurrently, .{ allocator, @as(u8, @intCast(i)) });
+    }
+    for (threads) |thread| {
+        thread.join();
+    }
+
+    var expected: usize = 0;
+    for (0..1000) |i| expected += 1 + i % 64;
+    try testing.expectEqual(4 * expected, allocator_instance.stats().used);
+}
+
+test "ArenaSmpAllocator single-threaded" {
+    var allocator_instance = arena_smp_allocator.ArenaSmpAllocator.init(.{ .thread_safe = false });
+    defer allocator_instance.deinit();
+
+    try allocateConcurrently(allocator_instance.allocator(), 7);
+    try testing.expect(allocator_instance.stats().used > 0);
+}
@@ -0,0 +1,48 @@
+const std = @import("std");
+const testing = std.testing;
+const zig_alloc = @import("zig-alloc");
+const c_allocator = zig_alloc.c;
+
+test "CAllocator basic functionality" {
+    inline for (.{ false, true }) |raw| {
+        var allocator_instance = c_allocator.CAllocator.init(.{ .raw = raw });
+        defer allocator_instance.deinit();
+
+        const allocator = allocator_instance.allocator();
+
+        var ptr = try allocator.alloc(u8, 100);
+        @memset(ptr, 0xAB);
+
+        ptr = try allocator.realloc(ptr, 1000);
+        defer allocator.free(ptr);
+        try testing.expectEqual(@as(u8, 0xAB), ptr[99]);
+    }
+}
+
+test "CAllocator alignment" {
+    // Past `max_align_t`, the raw allocator goes through `c_allocator`
+    inline for (.{ false, true }) |raw| {
+        var allocator_instance = c_allocator.CAllocator.init(.{ .raw = raw });
+        defer allocator_instance.deinit();
+
+        const allocator = allocator_instance.allocator();
+
+        var ptr = try allocator.alignedAlloc(u8, .@"64", 100);
+        try testing.expect(std.mem.isAligned(@intFromPtr(ptr.ptr), 64));
+
+        ptr = try allocator.realloc(ptr, 1000);
+        defer allocator.free(ptr);
+        try testing.expect(std.mem.isAligned(@intFromPtr(ptr.ptr), 64));
+    }
+}
+
+test "CAllocator through FfiAllocator" {
+    const ffi_allocator = try zig_alloc.ffi.createWithConfig(c_allocator.CAllocator, .{ .raw = true });
+    defer ffi_allocator.destroy();
+
+    const allocator = ffi_allocator.allocator;
+
+    const ptr = try allocator.alloc(u64, 64);
+    defer allocator.free(ptr);
+    @memset(ptr, 7);
+}
@@ -4,7 +4,7 @@ const zig_alloc = @import("zig-alloc");
 const debug_allocator = zig_alloc.debug;
 
 test "DebugAllocator basic functionality" {
-    var allocator_instance = debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false }); // Don't panic on leaks for tests
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false }); // Don't panic on leaks for tests
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -25,7 +25,7 @@ test "DebugAllocator basic functionality" {
 }
 
 test "DebugAllocator multiple allocations" {
-    var allocator_instance = debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -57,7 +57,7 @@ test "DebugAllocator multiple allocations" {
 }
 
 test "DebugAllocator realloc" {
-    var allocator_instance = debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -86,7 +86,7 @@ test "DebugAllocator realloc" {
 }
 
 test "DebugAllocator no leaks" {
-    var allocator_instance = debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -103,7 +103,7 @@ test "DebugAllocator no leaks" {
 }
 
 test "DebugAllocator alignment" {
-    var allocator_instance = debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
     defer allocator_instance.deinit();
     
     const allocator = allocator_instance.allocator();
@@ -120,4 +120,261 @@ test "DebugAllocator alignment" {
     const ptr3 = try allocator.alignedAlloc(u8, .@"16", 100);
     defer allocator.free(ptr3);
     try testing.expect(@intFromPtr(ptr3.ptr) % 16 == 0);
-}
\ No newline at end of file
+}
+test "DebugAllocator rejects unsupported stack trace depth" {
+    const result = debug_allocator.DebugAllocator.init(.{
+        .panic_on_leaks = false,
+        .stack_trace_frames = debug_allocator.max_stack_trace_frames + 1,
+    });
+    try testing.expectError(error.InvalidConfig, result);
+}
+
+test "DebugAllocator variants" {
+    for (debug_allocator.stack_trace_frame_variants) |stack_trace_frames| {
+        var allocator_instance = try debug_allocator.DebugAllocator.init(.{
+            .panic_on_leaks = false,
+            .stack_trace_frames = stack_trace_frames,
+            .never_unmap = true,
+            .retain_metadata = true,
+            .thread_safe = false,
+        });
+        defer allocator_instance.deinit();
+
+        const allocator = allocator_instance.allocator();
+
+        const ptr = try allocator.alloc(u8, 100);
+        allocator.free(ptr);
+    }
+}
+
+test "DebugAllocator memory limit" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{
+        .panic_on_leaks = false,
+        .memory_limit = 1024,
+    });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const ptr = try allocator.alloc(u8, 1000);
+    defer allocator.free(ptr);
+
+    try testing.expectError(error.OutOfMemory, allocator.alloc(u8, 100));
+}
+
+test "DebugAllocator memory limit changed at runtime" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const ptr = try allocator.alloc(u8, 1000);
+    defer allocator.free(ptr);
+    try testing.expectEqual(@as(usize, 1000), allocator_instance.totalRequestedBytes());
+
+    allocator_instance.setMemoryLimit(1024);
+    try testing.expectError(error.OutOfMemory, allocator.alloc(u8, 100));
+
+    allocator_instance.setMemoryLimit(null);
+    const more = try allocator.alloc(u8, 100);
+    defer allocator.free(more);
+    try testing.expectEqual(@as(usize, 1100), allocator_instance.totalRequestedBytes());
+}
+
+test "DebugAllocator lists live allocations" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const freed = try allocator.alloc(u8, 10);
+    allocator.free(freed);
+
+    const ptr = try allocator.alignedAlloc(u8, .@"16", 100);
+    defer allocator.free(ptr);
+
+    const live_allocations = try allocator_instance.liveAllocations(0);
+    defer std.heap.c_allocator.free(live_allocations);
+
+    try testing.expectEqual(@as(usize, 1), live_allocations.len);
+    try testing.expectEqual(@intFromPtr(ptr.ptr), live_allocations[0].address);
+    try testing.expectEqual(@as(usize, 100), live_allocations[0].size);
+    try testing.expectEqual(@as(usize, 16), live_allocations[0].alignment);
+}
+
+test "DebugAllocator records allocation stack traces" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false, .stack_trace_frames = 6 });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const ptr = try allocator.create(u64);
+    defer allocator.destroy(ptr);
+
+    const live_allocations = try allocator_instance.liveAllocations(0);
+    defer std.heap.c_allocator.free(live_allocations);
+
+    try testing.expectEqual(@as(usize, 1), live_allocations.len);
+    if (std.debug.sys_can_stack_trace) {
+        try testing.expect(live_allocations[0].trace_len > 0);
+    }
+    try testing.expect(live_allocations[0].trace_len <= 6);
+}
+
+test "DebugAllocator tracks resized allocations" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    var ptr = try allocator.alloc(u8, 10);
+    ptr = try allocator.realloc(ptr, 5000);
+    defer allocator.free(ptr);
+
+    const live_allocations = try allocator_instance.liveAllocations(0);
+    defer std.heap.c_allocator.free(live_allocations);
+
+    try testing.expectEqual(@as(usize, 1), live_allocations.len);
+    try testing.expectEqual(@intFromPtr(ptr.ptr), live_allocations[0].address);
+    try testing.expectEqual(@as(usize, 5000), live_allocations[0].size);
+}
+
+test "DebugAllocator hands leaks over" {
+    // Panicking on leaks proves taken leaks aren't reported again on deinit
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = true });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const leaked = try allocator.create(u64);
+
+    const leaks = try allocator_instance.takeLeaks();
+    defer std.heap.c_allocator.free(leaks);
+
+    try testing.expectEqual(@as(usize, 1), leaks.len);
+    try testing.expectEqual(@intFromPtr(leaked), leaks[0].address);
+    try testing.expectEqual(@as(usize, @sizeOf(u64)), leaks[0].size);
+}
+
+test "DebugAllocator lists allocations since a checkpoint" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const before = try allocator.alloc(u8, 10);
+    defer allocator.free(before);
+
+    const checkpoint = allocator_instance.checkpoint();
+
+    const after = try allocator.alloc(u8, 20);
+
+    const since_checkpoint = try allocator_instance.liveAllocations(checkpoint);
+    defer std.heap.c_allocator.free(since_checkpoint);
+
+    try testing.expectEqual(@as(usize, 1), since_checkpoint.len);
+    try testing.expectEqual(@intFromPtr(after.ptr), since_checkpoint[0].address);
+
+    allocator.free(after);
+
+    const after_free = try allocator_instance.liveAllocations(checkpoint);
+    defer std.heap.c_allocator.free(after_free);
+
+    try testing.expectEqual(@as(usize, 0), after_free.len);
+}
+
+test "DebugAllocator skips allocations marked static" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const static_memory = try allocator.alloc(u8, 16);
+    defer allocator.free(static_memory);
+    try testing.expect(allocator_instance.markStatic(@intFromPtr(static_memory.ptr)));
+
+    const live = try allocator_instance.liveAllocations(0);
+    defer std.heap.c_allocator.free(live);
+
+    try testing.expectEqual(@as(usize, 0), live.len);
+    try testing.expect(!allocator_instance.markStatic(0xdead));
+}
+
+test "DebugAllocator keeps the sequence of moved allocations" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    var before = try allocator.alloc(u8, 16);
+    var static_memory = try allocator.alloc(u8, 16);
+    try testing.expect(allocator_instance.markStatic(@intFromPtr(static_memory.ptr)));
+
+    const checkpoint = allocator_instance.checkpoint();
+
+    // Grown past their size class, both allocations move
+    before = try allocator.realloc(before, 5000);
+    defer allocator.free(before);
+    static_memory = try allocator.realloc(static_memory, 5000);
+    defer allocator.free(static_memory);
+
+    const since_checkpoint = try allocator_instance.liveAllocations(checkpoint);
+    defer std.heap.c_allocator.free(since_checkpoint);
+    try testing.expectEqual(@as(usize, 0), since_checkpoint.len);
+
+    const live = try allocator_instance.liveAllocations(0);
+    defer std.heap.c_allocator.free(live);
+    try testing.expectEqual(@as(usize, 1), live.len);
+    try testing.expectEqual(@intFromPtr(before.ptr), live[0].address);
+    try testing.expectEqual(@as(usize, 5000), live[0].size);
+}
+
+test "DebugAllocator over another FfiAllocator" {
+    const failing = try zig_alloc.ffi.createWithConfig(zig_alloc.failing.FailingAllocator, .{});
+    defer failing.destroy();
+    const failing_allocator = zig_alloc.ffi.opaquePtrToParent(zig_alloc.failing.FailingAllocator, failing).?;
+
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .backing_allocator = failing });
+    const allocator = allocator_instance.allocator();
+
+    allocator.free(try allocator.alloc(u8, 100));
+
+    // The debug allocator pages come from the failing allocator
+    const stats = failing_allocator.getStats();
+    try testing.expect(stats.allocations > 0);
+
+    allocator_instance.deinit();
+    const released = failing_allocator.getStats();
+    try testing.expectEqual(released.allocated_bytes, released.freed_bytes);
+}
+
+/// Event callback counting the events of each kind
+fn countEvent(event: *const debug_allocator.Event, context: ?*anyopaque) callconv(.c) void {
+    const counts: *[3]usize = @ptrCast(@alignCast(context.?));
+    counts[@intCast(@intFromEnum(event.kind))] += 1;
+}
+
+test "DebugAllocator classifies memory errors from its live allocations" {
+    var allocator_instance = try debug_allocator.DebugAllocator.init(.{ .panic_on_leaks = false, .never_unmap = true, .retain_metadata = true });
+    defer allocator_instance.deinit();
+
+    var counts: [3]usize = @splat(0);
+    allocator_instance.setEventCallback(countEvent, &counts);
+
+    const allocator = allocator_instance.allocator();
+
+    const freed = try allocator.alloc(u8, 16);
+    allocator.free(freed);
+    allocator.free(freed);
+    try testing.expectEqual(@as(usize, 1), counts[@intFromEnum(debug_allocator.EventKind.double_free)]);
+
+    // Freed with the wrong size, released anyway once reported
+    const wrong_size = try allocator.alloc(u8, 64);
+    allocator.rawFree(wrong_size[0..32], .@"1", @returnAddress());
+    try testing.expectEqual(@as(usize, 1), counts[@intFromEnum(debug_allocator.EventKind.invalid_free)]);
+    try testing.expectEqual(@as(usize, 0), allocator_instance.totalRequestedBytes());
+
+    const live_allocations = try allocator_instance.liveAllocations(0);
+    defer std.heap.c_allocator.free(live_allocations);
+    try testing.expectEqual(@as(usize, 0), live_allocations.len);
+}
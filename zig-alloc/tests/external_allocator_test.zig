@@ -0,0 +1,103 @@
+const std = @import("std");
+const testing = std.testing;
+const zig_alloc = @import("zig-alloc");
+const external_allocator = zig_alloc.external;
+
+/// Host allocator counting its live bytes, forwarding to the C allocator
+const Host = struct {
+    live_bytes: usize = 0,
+    reallocs: usize = 0,
+
+    const config_template: external_allocator.ExternalAllocator.Config = .{
+        .alloc = alloc,
+        .realloc = realloc,
+        .free = free,
+    };
+
+    fn config(self: *Host) external_allocator.ExternalAllocator.Config {
+        var result = config_template;
+        result.context = self;
+        return result;
+    }
+
+    fn alloc(context: ?*anyopaque, len: usize, alignment: usize) callconv(.c) ?[*]u8 {
+        const self: *Host = @ptrCast(@alignCast(context.?));
+        const memory = std.heap.c_allocator.rawAlloc(len, .fromByteUnits(alignment), 0) orelse return null;
+        self.live_bytes += len;
+        return memory;
+    }
+
+    fn realloc(context: ?*anyopaque, memory: [*]u8, len: usize, alignment: usize, new_len: usize) callconv(.c) ?[*]u8 {
+        const self: *Host = @ptrCast(@alignCast(context.?));
+        const new_memory = alloc(context, new_len, alignment) orelse return null;
+        @memcpy(new_memory[0..@min(len, new_len)], memory[0..@min(len, new_len)]);
+        free(context, memory, len, alignment);
+        self.reallocs += 1;
+        return new_memory;
+    }
+
+    fn free(context: ?*anyopaque, memory: [*]u8, len: usize, alignment: usize) callconv(.c) void {
+        const self: *Host = @ptrCast(@alignCast(context.?));
+        std.heap.c_allocator.rawFree(memory[0..len], .fromByteUnits(alignment), 0);
+        self.live_bytes -= len;
+    }
+};
+
+test "ExternalAllocator calls the host functions" {
+    var host: Host = .{};
+    var allocator_instance = try external_allocator.ExternalAllocator.init(host.config());
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    var memory = try allocator.alloc(u8, 100);
+    @memset(memory, 0xAB);
+    try testing.expectEqual(@as(usize, 100), host.live_bytes);
+
+    // Growing moves the memory through the host
+    memory = try allocator.realloc(memory, 300);
+    try testing.expectEqual(@as(usize, 300), host.live_bytes);
+    try testing.expectEqual(@as(usize, 1), host.reallocs);
+    try testing.expectEqual(@as(u8, 0xAB), memory[99]);
+
+    allocator.free(memory);
+    try testing.expectEqual(@as(usize, 0), host.live_bytes);
+}
+
+test "ExternalAllocator requires every function" {
+    try testing.expectError(error.InvalidConfig, external_allocator.ExternalAllocator.init(.{}));
+    try testing.expectError(error.InvalidConfig, external_allocator.ExternalAllocator.init(.{
+        .alloc = Host.alloc,
+        .free = Host.free,
+    }));
+}
+
+test "ExternalAllocator backs an arena" {
+    var host: Host = .{};
+    const external = try zig_alloc.ffi.createWithConfig(external_allocator.ExternalAllocator, host.config());
+    defer external.destroy();
+
+    var arena = zig_alloc.arena_smp.ArenaSmpAllocator.init(.{ .backing_allocator = external });
+    _ = try arena.allocator().alloc(u8, 100);
+    try testing.expectEqual(arena.stats().capacity, host.live_bytes);
+
+    arena.deinit();
+    try testing.expectEqual(@as(usize, 0), host.live_bytes);
+}
+
+test "ExternalAllocator backs a memory pool" {
+    var host: Host = .{};
+    const external = try zig_alloc.ffi.createWithConfig(external_allocator.ExternalAllocator, host.config());
+    defer external.destroy();
+
+    var pool = try zig_alloc.memory_pool.MemoryPool.init(.{
+        .backing_allocator = external,
+        .item_size = @sizeOf(u64),
+        .item_alignment = @alignOf(u64),
+        .preheat = 4,
+    });
+    try testing.expect(host.live_bytes > 0);
+
+    pool.deinit();
+    try testing.expectEqual(@as(usize, 0), host.live_bytes);
+}
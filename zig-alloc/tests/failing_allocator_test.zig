@@ -0,0 +1,61 @@
+const std = @import("std");
+const testing = std.testing;
+const zig_alloc = @import("zig-alloc");
+const failing_allocator = zig_alloc.failing;
+
+test "FailingAllocator fails from the given allocation on" {
+    var allocator_instance = try failing_allocator.FailingAllocator.init(.{ .fail_index = 1 });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const first = try allocator.alloc(u8, 16);
+    defer allocator.free(first);
+    try testing.expectError(error.OutOfMemory, allocator.alloc(u8, 16));
+
+    try testing.expectError(error.OutOfMemory, allocator.alloc(u8, 16));
+
+    const stats = allocator_instance.getStats();
+    try testing.expectEqual(@as(usize, 1), stats.allocations);
+    try testing.expectEqual(@as(usize, 0), stats.deallocations);
+    try testing.expectEqual(@as(usize, 16), stats.allocated_bytes);
+    try testing.expectEqual(@as(usize, 2), stats.failures);
+}
+
+test "FailingAllocator byte limit" {
+    var allocator_instance = try failing_allocator.FailingAllocator.init(.{ .byte_limit = 100 });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const first = try allocator.alloc(u8, 60);
+    try testing.expectError(error.OutOfMemory, allocator.alloc(u8, 60));
+
+    // Freed bytes still count against the limit
+    allocator.free(first);
+    try testing.expectError(error.OutOfMemory, allocator.alloc(u8, 60));
+    allocator.free(try allocator.alloc(u8, 40));
+}
+
+test "FailingAllocator seeded failures are reproducible" {
+    var failures: [2]usize = undefined;
+
+    for (&failures) |*count| {
+        var allocator_instance = try failing_allocator.FailingAllocator.init(.{ .failure_probability = 0.5, .seed = 42 });
+        defer allocator_instance.deinit();
+
+        const allocator = allocator_instance.allocator();
+        for (0..100) |_| {
+            const bytes = allocator.alloc(u8, 8) catch continue;
+            allocator.free(bytes);
+        }
+        count.* = allocator_instance.getStats().failures;
+    }
+
+    try testing.expect(failures[0] > 0 and failures[0] < 100);
+    try testing.expectEqual(failures[0], failures[1]);
+}
+
+test "FailingAllocator rejects invalid probability" {
+    try testing.expectError(error.InvalidConfig, failing_allocator.FailingAllocator.init(.{ .failure_probability = 2 }));
+}
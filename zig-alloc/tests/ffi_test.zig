@@ -3,6 +3,7 @@ const testing = std.testing;
 const zig_alloc = @import("zig-alloc");
 const ffi = zig_alloc.ffi;
 const smp_allocator = zig_alloc.smp;
+const arena_smp_allocator = zig_alloc.arena_smp;
 
 test "FfiAllocator basic operations" {
     // Test with SMP allocator as backing allocator
@@ -119,6 +120,69 @@ test "FfiAllocator realloc shrink" {
     }
 }
 
+test "FfiAllocator realloc resizes in place" {
+    const ffi_allocator = try ffi.create(smp_allocator.SmpAllocator);
+    defer ffi_allocator.destroy();
+
+    const ptr = ffi_allocator.alloc(100, .fromByteUnits(8));
+    try testing.expect(ptr != null);
+
+    if (ptr) |p| {
+        // Both sizes share the SMP allocator's 128 byte size class, so the
+        // memory must be resized without moving.
+        const grown = ffi_allocator.realloc(p, 100, .fromByteUnits(8), 120, .fromByteUnits(8));
+        try testing.expect(grown == p);
+
+        const shrunk = ffi_allocator.realloc(p, 120, .fromByteUnits(8), 90, .fromByteUnits(8));
+        try testing.expect(shrunk == p);
+
+        ffi_allocator.free(p, 90, .fromByteUnits(8));
+    }
+}
+
+test "FfiAllocator realloc grows arena tail in place" {
+    const ffi_allocator = try ffi.createWithConfig(arena_smp_allocator.ArenaSmpAllocator, .{});
+    defer ffi_allocator.destroy();
+
+    const ptr = ffi_allocator.alloc(16, .fromByteUnits(8));
+    try testing.expect(ptr != null);
+
+    if (ptr) |p| {
+        // The most recent arena allocation can be extended within its buffer.
+        const grown = ffi_allocator.realloc(p, 16, .fromByteUnits(8), 32, .fromByteUnits(8));
+        try testing.expect(grown == p);
+    }
+}
+
+test "FfiAllocator realloc with new alignment copies" {
+    const ffi_allocator = try ffi.create(smp_allocator.SmpAllocator);
+    defer ffi_allocator.destroy();
+
+    const ptr = ffi_allocator.alloc(32, .fromByteUnits(1));
+    try testing.expect(ptr != null);
+
+    if (ptr) |p| {
+        const bytes = @as([*]u8, @ptrCast(p));
+        for (0..32) |i| {
+            bytes[i] = @intCast(i);
+        }
+
+        const new_ptr = ffi_allocator.realloc(p, 32, .fromByteUnits(1), 64, .fromByteUnits(64));
+        try testing.expect(new_ptr != null);
+
+        if (new_ptr) |np| {
+            try testing.expect(@intFromPtr(np) % 64 == 0);
+
+            const new_bytes = @as([*]u8, @ptrCast(np));
+            for (0..32) |i| {
+                try testing.expect(new_bytes[i] == @as(u8, @intCast(i)));
+            }
+
+            ffi_allocator.free(np, 64, .fromByteUnits(64));
+        }
+    }
+}
+
 test "FfiAllocator realloc from zero" {
     const ffi_allocator = try ffi.create(smp_allocator.SmpAllocator);
     defer ffi_allocator.destroy();
@@ -0,0 +1,39 @@
+const std = @import("std");
+const testing = std.testing;
+const zig_alloc = @import("zig-alloc");
+const fixed_buffer_allocator = zig_alloc.fixed_buffer;
+
+test "FixedBufferAllocator basic functionality" {
+    var buffer: [256]u8 = undefined;
+    var allocator_instance = try fixed_buffer_allocator.FixedBufferAllocator.init(.{ .buffer = &buffer, .len = buffer.len });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const ptr = try allocator.alloc(u8, 100);
+    try testing.expect(@intFromPtr(ptr.ptr) >= @intFromPtr(&buffer));
+    try testing.expect(@intFromPtr(ptr.ptr) + ptr.len <= @intFromPtr(&buffer) + buffer.len);
+    try testing.expectEqual(@as(usize, 100), allocator_instance.endIndex());
+
+    // The most recent allocation is given back
+    allocator.free(ptr);
+    try testing.expectEqual(@as(usize, 0), allocator_instance.endIndex());
+}
+
+test "FixedBufferAllocator out of memory" {
+    var buffer: [64]u8 = undefined;
+    var allocator_instance = try fixed_buffer_allocator.FixedBufferAllocator.init(.{ .buffer = &buffer, .len = buffer.len });
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    _ = try allocator.alloc(u8, 48);
+    try testing.expectError(error.OutOfMemory, allocator.alloc(u8, 32));
+
+    allocator_instance.reset();
+    _ = try allocator.alloc(u8, 64);
+}
+
+test "FixedBufferAllocator requires a buffer" {
+    try testing.expectError(error.InvalidConfig, fixed_buffer_allocator.FixedBufferAllocator.init(.{}));
+}
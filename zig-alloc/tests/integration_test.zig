@@ -21,10 +21,10 @@ test "Integration: All allocator types can be created" {
     var smp_allocator = zig_alloc.smp.SmpAllocator.init();
     defer smp_allocator.deinit();
     
-    var debug_allocator = zig_alloc.debug.DebugAllocator.init(.{ .panic_on_leaks = false });
+    var debug_allocator = try zig_alloc.debug.DebugAllocator.init(.{ .panic_on_leaks = false });
     defer debug_allocator.deinit();
     
-    var arena_allocator = zig_alloc.arena_smp.ArenaSmpAllocator.init();
+    var arena_allocator = zig_alloc.arena_smp.ArenaSmpAllocator.init(.{});
     defer arena_allocator.deinit();
     
     // Test that they all provide allocator interface
@@ -0,0 +1,51 @@
+const std = @import("std");
+const testing = std.testing;
+const zig_alloc = @import("zig-alloc");
+const memory_pool = zig_alloc.memory_pool;
+
+test "MemoryPool reuses freed items" {
+    var pool = try memory_pool.MemoryPool.init(.{ .item_size = 24, .item_alignment = 8 });
+    defer pool.deinit();
+
+    const first = pool.create().?;
+    try testing.expect(@intFromPtr(first) % 8 == 0);
+
+    pool.destroy(first);
+    try testing.expectEqual(first, pool.create().?);
+}
+
+test "MemoryPool fixed capacity" {
+    var pool = try memory_pool.MemoryPool.init(.{ .item_size = 16, .preheat = 2, .growable = false });
+    defer pool.deinit();
+
+    const first = pool.create().?;
+    _ = pool.create().?;
+    try testing.expect(pool.create() == null);
+
+    pool.destroy(first);
+    try testing.expect(pool.create() != null);
+
+    // Resetting brings the preheated items back
+    try testing.expect(pool.reset());
+    _ = pool.create().?;
+    _ = pool.create().?;
+    try testing.expect(pool.create() == null);
+}
+
+test "MemoryPool allocator interface" {
+    var pool = try memory_pool.MemoryPool.init(.{ .item_size = @sizeOf(u64), .item_alignment = @alignOf(u64) });
+    defer pool.deinit();
+
+    const allocator = pool.allocator();
+
+    const item = try allocator.create(u64);
+    item.* = 42;
+    allocator.destroy(item);
+
+    // Larger than an item
+    try testing.expectError(error.OutOfMemory, allocator.create([4]u64));
+}
+
+test "MemoryPool rejects invalid alignment" {
+    try testing.expectError(error.InvalidConfig, memory_pool.MemoryPool.init(.{ .item_size = 8, .item_alignment = 3 }));
+}
@@ -0,0 +1,45 @@
+const std = @import("std");
+const testing = std.testing;
+const zig_alloc = @import("zig-alloc");
+const page_allocator = zig_alloc.page;
+
+test "PageAllocator basic functionality" {
+    var allocator_instance = page_allocator.PageAllocator.init();
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const ptr = try allocator.alloc(u8, 100);
+    defer allocator.free(ptr);
+
+    for (ptr, 0..) |*byte, i| {
+        byte.* = @intCast(i % 256);
+    }
+
+    for (ptr, 0..) |byte, i| {
+        try testing.expect(byte == @as(u8, @intCast(i % 256)));
+    }
+}
+
+test "PageAllocator page alignment" {
+    var allocator_instance = page_allocator.PageAllocator.init();
+    defer allocator_instance.deinit();
+
+    const allocator = allocator_instance.allocator();
+
+    const ptr = try allocator.alloc(u8, 1);
+    defer allocator.free(ptr);
+
+    try testing.expect(std.mem.isAligned(@intFromPtr(ptr.ptr), std.heap.pageSize()));
+}
+
+test "PageAllocator through FfiAllocator" {
+    const ffi_allocator = try zig_alloc.ffi.create(page_allocator.PageAllocator);
+    defer ffi_allocator.destroy();
+
+    const allocator = ffi_allocator.allocator;
+
+    const ptr = try allocator.alloc(u64, 512);
+    defer allocator.free(ptr);
+    @memset(ptr, 0xAB);
+}
@@ -103,4 +103,4 @@ test "SmpAllocator alignment" {
     const ptr3 = try allocator.alignedAlloc(u8, .@"16", 100);
     defer allocator.free(ptr3);
     try testing.expect(@intFromPtr(ptr3.ptr) % 16 == 0);
-}
\ No newline at end of file
+}
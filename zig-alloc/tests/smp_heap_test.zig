@@ -0,0 +1,183 @@
+const std = @import("std");
+const testing = std.testing;
+const zig_alloc = @import("zig-alloc");
+const smp_heap = zig_alloc.smp_heap;
+
+test "SmpHeap basic functionality" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    const allocator = heap.allocator();
+
+    const small = try allocator.alloc(u8, 100);
+    defer allocator.free(small);
+    @memset(small, 0xAB);
+
+    const large = try allocator.alloc(u8, 1024 * 1024);
+    defer allocator.free(large);
+    @memset(large, 0xCD);
+
+    try testing.expectEqual(@as(u8, 0xAB), small[99]);
+    try testing.expectEqual(@as(u8, 0xCD), large[1024 * 1024 - 1]);
+}
+
+test "SmpHeap reuses freed slots" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    const allocator = heap.allocator();
+
+    const first = try allocator.alloc(u64, 4);
+    allocator.free(first);
+
+    const second = try allocator.alloc(u64, 4);
+    defer allocator.free(second);
+    try testing.expectEqual(first.ptr, second.ptr);
+}
+
+test "SmpHeap instances are isolated" {
+    var first_heap = smp_heap.SmpHeap.init();
+    defer first_heap.deinit();
+    var second_heap = smp_heap.SmpHeap.init();
+    defer second_heap.deinit();
+
+    const first = try first_heap.allocator().alloc(u8, 64);
+    first_heap.allocator().free(first);
+
+    // The slot freed in the first heap is not handed out by the second one
+    const second = try second_heap.allocator().alloc(u8, 64);
+    defer second_heap.allocator().free(second);
+    try testing.expect(first.ptr != second.ptr);
+}
+
+test "SmpHeap tracks mapped memory" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    const allocator = heap.allocator();
+    try testing.expectEqual(@as(usize, 0), heap.mappedBytes());
+
+    const small = try allocator.alloc(u8, 16);
+    const slab_bytes = heap.mappedBytes();
+    try testing.expect(slab_bytes > 0);
+
+    var large = try allocator.alloc(u8, 1024 * 1024);
+    try testing.expectEqual(slab_bytes + 1024 * 1024, heap.mappedBytes());
+
+    large = try allocator.realloc(large, 4 * 1024 * 1024);
+    try testing.expectEqual(slab_bytes + 4 * 1024 * 1024, heap.mappedBytes());
+
+    allocator.free(large);
+    try testing.expectEqual(slab_bytes, heap.mappedBytes());
+
+    // Slabs stay mapped until the heap is deinitialized, live slots included
+    allocator.free(small);
+    try testing.expectEqual(slab_bytes, heap.mappedBytes());
+}
+
+test "SmpHeap thread safe" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    const allocator = heap.allocator();
+
+    const worker = struct {
+        fn run(worker_allocator: std.mem.Allocator, seed: u8) !void {
+            for (0..1000) |i| {
+                const bytes = try worker_allocator.alloc(u8, 1 + i % 512);
+                defer worker_allocator.free(bytes);
+                @memset(bytes, seed);
+                try testing.expectEqual(seed, bytes[bytes.len - 1]);
+            }
+        }
+    };
+
+    var threads: [4]std.Thread = undefined;
+    for (&threads, 0..) |*thread, i| {
+        thread.* = try std.Thread.spawn(.{}, worker.run, .{ allocator, @as(u8, @intCast(i)) });
+    }
+    for (threads) |thread| {
+        thread.join();
+    }
+}
+
+test "SmpHeap trim unmaps empty slabs" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    const allocator = heap.allocator();
+
+    var slots: [64]*[256]u8 = undefined;
+    for (&slots) |*slot| {
+        slot.* = try allocator.create([256]u8);
+    }
+    const mapped = heap.mappedBytes();
+    for (slots) |slot| {
+        allocator.destroy(slot);
+    }
+
+    try testing.expect(heap.trim() >= mapped);
+    try testing.expectEqual(@as(usize, 0), heap.mappedBytes());
+
+    // The heap maps new slabs afterwards
+    const bytes = try allocator.alloc(u8, 256);
+    defer allocator.free(bytes);
+    try testing.expect(heap.mappedBytes() > 0);
+}
+
+test "SmpHeap trim keeps slots in use" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    const allocator = heap.allocator();
+
+    const kept = try allocator.alloc(u8, 16 * 1024);
+    defer allocator.free(kept);
+    @memset(kept, 0xAB);
+
+    const freed = try allocator.alloc(u8, 16 * 1024);
+    allocator.free(freed);
+
+    // Only the pages of the free slot past its first one are discarded
+    const released = heap.trim();
+    try testing.expect(released < 16 * 1024);
+    try testing.expect(heap.mappedBytes() > 0);
+    try testing.expectEqual(@as(u8, 0xAB), kept[kept.len - 1]);
+
+    // The free slot is still handed out
+    const reused = try allocator.alloc(u8, 16 * 1024);
+    defer allocator.free(reused);
+    try testing.expectEqual(freed.ptr, reused.ptr);
+}
+
+test "SmpHeap trim counts discarded pages once" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    const allocator = heap.allocator();
+
+    const kept = try allocator.alloc(u8, 16 * 1024);
+    const freed = try allocator.alloc(u8, 16 * 1024);
+    allocator.free(freed);
+    const mapped = heap.mappedBytes();
+
+    const discarded = heap.trim();
+    try testing.expectEqual(@as(usize, 0), heap.trim());
+
+    // Reused and freed again, its pages count once more
+    const reused = try allocator.alloc(u8, 16 * 1024);
+    @memset(reused, 0xAB);
+    allocator.free(reused);
+    try testing.expectEqual(discarded, heap.trim());
+
+    // Unmapping the slab only counts what wasn't given back yet
+    allocator.free(kept);
+    try testing.expectEqual(mapped, discarded + heap.trim());
+}
+
+test "SmpHeap trim of an empty heap" {
+    var heap = smp_heap.SmpHeap.init();
+    defer heap.deinit();
+
+    try testing.expectEqual(@as(usize, 0), heap.trim());
+}